@@ -7,23 +7,39 @@ mod utils;
 
 
 // Importing the public endpoints of our utils
-use crate::utils::db::{add_score_entry, DB, get_all, get_top, ScoreEntry, set_score_schema};
+use crate::utils::db::{add_review, add_score_entry, DB, finish_review, get_all, get_rating, get_review, get_top, ScoreEntry, Scoreboard, set_rating_schema, set_review_schema, set_score_accuracy, set_score_schema, update_rating};
 use crate::utils::session::{find_session, remove_session, SessionHandler, add_session};
-use crate::utils::game::{COLOR, DIFFICULTY, find_with_auto_promotion, Game};
-use crate::utils::errors::external::{FenResponse, OkOrResponse, Response};
-use crate::utils::requests::GameSettings;
+use crate::utils::exhibition::{ExhibitionHandler, ExhibitionState};
+use crate::utils::game::{Analysis, BookConfig, BotConfig, DEFAULT_ENGINE, COLOR, DIFFICULTY, DifficultyLimits, EngineConfig, find_with_auto_promotion, Game, GameClock, GameError, GeneratorBackend, GeneratorConfig, GeneratorFactory, Handicap, Odds, Opponent, PoolStats, probe_tablebase, review_game, GameAccuracy, SideAccuracy, StartPosition, TablebaseVerdict, ThinkingEvent, WdlProbabilities};
+use crate::utils::errors::external::{JsonOrFenResponse, MoveResponse, Response};
+use crate::utils::requests::{ExhibitionSettings, GameSettings};
+use crate::utils::responses::{BotEntry, GameEnd, Hint, MoveReply, ReviewReport, StrengthRange};
 
 // Importing necessary modules and structures from the `rocket` and `shakmaty` crates.
 use rocket_dyn_templates::{context, Template};
-use shakmaty::{EnPassantMode, Position};
+use shakmaty::{CastlingMode, Chess, EnPassantMode, Position};
+use shakmaty::san::SanPlus;
 use rocket::fs::{FileServer, relative};
 use rocket::http::{CookieJar, Status};
-use rocket::serde::json::{Json};
+use rocket::serde::json::{Json, serde_json, Value};
 use rocket::response::Redirect;
+use rocket::response::stream::{Event, EventStream};
+use rocket::tokio::select;
+use rocket::tokio::sync::broadcast::error::RecvError;
+use rocket::Shutdown;
+use rocket::fairing::AdHoc;
 use rocket::form::Form;
 use shakmaty::fen::Fen;
 use shakmaty::uci::Uci;
+use shakmaty::variant::{Variant, VariantPosition};
 use rocket::State;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+// How long `/game_end` waits for the review of the game, the accuracy of a longer review follows at `/review/<id>`.
+const REVIEW_WAIT: Duration = Duration::from_secs(3);
 
 // Route handler for the root URL ("/"). Redirects to "/welcome_page.html"
 #[get("/")]
@@ -35,8 +51,8 @@ async fn get() -> Redirect {
 // It takes an optional `new:session` query parameter and `game_settings` form data.
 // It uses `CookieJar` to manage session cookies and a `SessionHandler` to manage the game session.
 #[post("/game", data="<game_settings>")]
-async fn post_game(game_settings: Form<GameSettings>, cookie_jar: &CookieJar<'_>, session_handler: &State<SessionHandler>) -> Response<Template> {
-    if let Some(_) = find_session(cookie_jar, session_handler).await {
+async fn post_game(game_settings: Form<GameSettings>, cookie_jar: &CookieJar<'_>, session_handler: &State<SessionHandler>, factory: &State<GeneratorFactory>, db: &State<DB>) -> Response<Template> {
+    if find_session(cookie_jar, session_handler).await.is_some() {
         // The user already has a session
         if game_settings.new_session.is_none() {
             // The user receives an error, because he was not intentionally requesting a new game
@@ -48,50 +64,236 @@ async fn post_game(game_settings: Form<GameSettings>, cookie_jar: &CookieJar<'_>
     // Creates game instance
     let color = COLOR::new(game_settings.color).ok_or((Status::BadRequest, "Your color submission is invalid"))?;
     let difficulty = DIFFICULTY::new(game_settings.difficulty).ok_or((Status::BadRequest, "Your difficulty submission is invalid"))?;
-    let game = Game::new(color.clone(), difficulty.clone(), game_settings.username.clone()).await.ok_or((Status::InternalServerError, "Game could not be created"))?;
+    // A chosen bot replaces the difficulty, it always plays at its configured elo
+    let (opponent, elo) = match game_settings.bot.as_deref().filter(|name| !name.is_empty()) {
+        Some(name) => {
+            let bot = factory.bot(name).ok_or((Status::BadRequest, "Your bot submission is invalid"))?;
+            if game_settings.elo.is_some() {
+                return Err((Status::BadRequest, "Bots play at their configured elo"));
+            }
+            (Opponent::Bot(bot.clone()), Some(bot.elo))
+        }
+        None => {
+            // Adaptive games start at the rating of the user
+            let default_elo = match difficulty {
+                DIFFICULTY::ADAPTIVE => {
+                    let conn = db.get().map_err(|_| (Status::InternalServerError, "Could not read rating"))?;
+                    let rating = get_rating(&conn, &game_settings.username).map_err(|_| (Status::InternalServerError, "Could not read rating"))?;
+                    rating.map_or(difficulty.parse_elo(), |(rating, _)| rating)
+                }
+                _ => difficulty.parse_elo()
+            };
+            let elo = factory.strength(game_settings.elo, default_elo).map_err(|_| (Status::BadRequest, "Your elo submission is not supported by the engine"))?;
+            // The rating can only follow the strength of the user, if the engine plays at a known elo
+            if matches!(difficulty, DIFFICULTY::ADAPTIVE) && elo.is_none() {
+                return Err((Status::BadRequest, "Adaptive games need an engine that can limit its strength"));
+            }
+            (Opponent::Difficulty(difficulty), elo)
+        }
+    };
+    // Odds games start with a piece less or an extra move for one side
+    let odds = match game_settings.odds.as_deref().filter(|odds| !odds.is_empty()) {
+        Some(name) => {
+            let handicap = Handicap::new(name).ok_or((Status::BadRequest, "Your odds submission is invalid"))?;
+            let giver = match game_settings.odds_giver {
+                Some('u') => color.parse_color(),
+                Some('e') => !color.parse_color(),
+                _ => return Err((Status::BadRequest, "Your odds submission is invalid"))
+            };
+            // The rating of adaptive games would not reflect the strength of the user anymore
+            if opponent.is_adaptive() {
+                return Err((Status::BadRequest, "Adaptive games can not be played with odds"));
+            }
+            Some(Odds { handicap, giver })
+        }
+        None => None
+    };
+    // Chess960 games start from a random position or the one with the chosen index
+    let start_position = match (game_settings.chess960.unwrap_or(false), odds) {
+        (false, Some(odds)) => StartPosition::Odds(odds),
+        (false, None) => StartPosition::Standard,
+        (true, Some(_)) => return Err((Status::BadRequest, "Chess960 games can not be played with odds")),
+        (true, None) => {
+            // The rating of adaptive games is based on standard games only
+            if opponent.is_adaptive() {
+                return Err((Status::BadRequest, "Adaptive games can not be played as Chess960"));
+            }
+            StartPosition::chess960(game_settings.chess960_index).ok_or((Status::BadRequest, "Your Chess960 position submission is invalid"))?
+        }
+    };
+    // Variants are played against the variant engine, which decides the available variants
+    let start_position = match game_settings.variant.as_deref().filter(|variant| !variant.is_empty() && *variant != Variant::Chess.uci()) {
+        Some(name) => {
+            let variant = Variant::from_uci(name).ok().filter(|variant| factory.variants().contains(variant)).ok_or((Status::BadRequest, "Your variant submission is not supported"))?;
+            if !matches!(start_position, StartPosition::Standard) {
+                return Err((Status::BadRequest, "Variants can not be played with odds or as Chess960"));
+            }
+            // Bots play with their own engine and the rating of adaptive games is based on standard games only
+            if !matches!(opponent, Opponent::Difficulty(_)) || opponent.is_adaptive() {
+                return Err((Status::BadRequest, "Variants can only be played at the easy, medium and hard difficulty"));
+            }
+            StartPosition::Variant(variant)
+        }
+        None => start_position
+    };
+    // Custom positions are played from their FEN, which is validated when the game is created
+    let start_position = match game_settings.fen.as_deref().map(str::trim).filter(|fen| !fen.is_empty()) {
+        Some(fen) => {
+            let fen = fen.parse().map_err(|_| (Status::BadRequest, "Your FEN could not be parsed"))?;
+            if !matches!(start_position, StartPosition::Standard) {
+                return Err((Status::BadRequest, "Custom positions can not be played with odds, as Chess960 or as a variant"));
+            }
+            // The rating of adaptive games is based on standard games only
+            if opponent.is_adaptive() {
+                return Err((Status::BadRequest, "Adaptive games can not be played from a custom position"));
+            }
+            StartPosition::Fen(fen)
+        }
+        None => start_position
+    };
+    // The variant engine has a range of its own, the game is scored with the elo it is played at
+    let elo = match (&start_position, &opponent) {
+        (StartPosition::Variant(_), Opponent::Difficulty(difficulty)) => factory.variant_strength(elo.unwrap_or(difficulty.parse_elo())),
+        _ => elo
+    };
+    let opponent_name = player_name(opponent.name(), elo);
+    let clock = game_settings.clock_minutes.filter(|minutes| *minutes > 0).map(|minutes| GameClock::new(minutes, game_settings.clock_increment.unwrap_or(0)));
+    let game = Game::new(color.clone(), opponent, elo, game_settings.username.clone(), clock, start_position, factory).await.map_err(|err| match err {
+        GameError::Position(reason) => (Status::BadRequest, reason),
+        GameError::Engine => (Status::InternalServerError, "Game could not be created"),
+        GameError::Flagged => (Status::BadRequest, "The engine ran out of time before its first move, please choose a longer time control"),
+    })?;
+    let fen = Fen::from_position(game.board.clone(), EnPassantMode::Legal).to_string();
+    // Custom positions with castling rights besides the corners are castled like Chess960 games
+    let chess960 = game.board.castles().mode() == CastlingMode::Chess960;
+    let variant = game.start_position.variant().uci();
 
     // Add game to the session handler and update cookies
     add_session(game, cookie_jar, session_handler).await;
     // Render game template with provided data
     Ok(Template::render("game", context! {
         username: game_settings.username.clone(),
-        difficulty: difficulty.parse_player_name(),
-        color: color.parse_code()
+        difficulty: opponent_name,
+        color: color.parse_code(),
+        fen,
+        chess960,
+        variant
     }))
 }
 
 // Route handler for "/game_end". It checks if the current game session is over.
 // It uses `CookieJar` to manage session cookies and a `SessionHandler` to manage sessions.
 #[get("/game_end")]
-async fn get_game_end(cookie_jar: &CookieJar<'_>, session_handler: &State<SessionHandler>, db: &State<DB>) -> OkOrResponse<String> {
+async fn get_game_end(cookie_jar: &CookieJar<'_>, session_handler: &State<SessionHandler>, db: &State<DB>, factory: &State<GeneratorFactory>) -> JsonOrFenResponse<GameEnd> {
     // Grabs the users session if it exists
     let session = find_session(cookie_jar, session_handler).await.ok_or((Status::BadRequest, String::from("You are missing a session key")))?;
     let game = session.get().await;
 
-    return if game.board.is_game_over() || game.board.halfmoves() >= 100 {
+    if let Some(outcome) = game.outcome() {
         let board = &game.board;
-        let outcome = board.outcome().ok_or((Status::InternalServerError, String::from("Contradictory game conditions")))?;
-        if let Some(winner) = outcome.winner() {
-            if game.user_color.to_string().eq(&winner.to_string()[..1]).clone() {
-                let score_entry = ScoreEntry::new(&game.username, board.fullmoves().get(), &game.difficulty);
+        // The review is added first, its id identifies the score entry of the game once the accuracy is known
+        let review_id = db.get().and_then(|conn| add_review(&conn, &game.username)).inspect_err(|err| error!("Could not add the review: {}", err)).ok();
+        let mut score = None;
+        let mut scored = None;
+        let ranked = game.is_ranked();
+        if let (Some(winner), Some(elo)) = (outcome.winner().filter(|_| ranked), game.elo) {
+            if game.user_color.to_string().eq(&winner.to_string()[..1]) {
+                let odds_multiplier = game.start_position.odds().map_or(1., |odds| odds.score_multiplier(winner));
+                let score_entry = ScoreEntry::new(&game.username, board.fullmoves().get(), elo, game.hints_used, odds_multiplier);
+                score = Some(score_entry.score);
                 let conn = &db.get().map_err(|_| (Status::InternalServerError, String::from("Could not add score!")))?;
-                add_score_entry(conn, score_entry).map_err(|_| (Status::InternalServerError, String::from("Could not add Score")))?;
+                // Chess960 games and every variant have a scoreboard of their own
+                let scoreboard = if game.start_position.is_chess960() { Scoreboard::Chess960 } else { Scoreboard::of(game.start_position.variant()) };
+                scored = Some(scoreboard);
+                add_score_entry(conn, score_entry, scoreboard, review_id).map_err(|_| (Status::InternalServerError, String::from("Could not add Score")))?;
             }
         }
+        // The rating of adaptive games is adjusted for every result, the next game starts at the new rating
+        let mut rating = None;
+        if let Some(elo) = game.elo.filter(|_| game.opponent.is_adaptive()) {
+            let result = match outcome.winner() {
+                Some(winner) if winner == game.user_turn() => 1.,
+                Some(_) => 0.,
+                None => 0.5,
+            };
+            let conn = &db.get().map_err(|_| (Status::InternalServerError, String::from("Could not update rating!")))?;
+            let new_rating = update_rating(conn, &game.username, DIFFICULTY::ADAPTIVE.parse_elo(), elo, result).map_err(|_| (Status::InternalServerError, String::from("Could not update rating")))?;
+            rating = Some(new_rating);
+        }
+        // The game is reviewed in the background, because the session is removed now
+        let review = review_id.map(|id| spawn_review(id, &game, scored, factory, db));
+        let mut game_end = GameEnd {
+            winner: outcome.winner().map(|winner| winner.fold_wb("white", "black")),
+            score,
+            hints_used: game.hints_used,
+            rating,
+            review_id,
+            accuracy: None,
+            ranked,
+        };
+        drop(game);
 
         remove_session(cookie_jar, session_handler).await;
-        Ok(Status::Ok)
+        // Short games are reviewed in time, otherwise the accuracy stays `None` and the review keeps running
+        if let Some(review) = review {
+            game_end.accuracy = tokio::time::timeout(REVIEW_WAIT, review).await.ok().and_then(Result::ok).flatten();
+        }
+        Ok(Json(game_end))
     } else {
         let fen = Fen::from_position(game.board.clone(), EnPassantMode::Legal).to_string();
         Err((Status::NotAcceptable, fen))
-    };
+    }
+}
+
+// Function to review a finished game in the background, whose pending review has already been added.
+// Once it is done, the accuracy of the winner is added to the score entry of the game on the scoreboard as well.
+// Returns the task, which yields the accuracy of both sides if the review succeeded.
+fn spawn_review(id: i64, game: &Game, scored: Option<Scoreboard>, factory: &GeneratorFactory, db: &DB) -> JoinHandle<Option<GameAccuracy>> {
+    let (factory, db) = (factory.clone(), db.clone());
+    let (start, moves, user) = (game.start.clone(), game.moves.clone(), game.user_turn());
+    tokio::spawn(async move {
+        let review = review_game(&factory, &start, &moves, user).await.inspect_err(|err| warn!("The review {} failed: {}", id, err)).ok();
+        let stored = db.get().and_then(|conn| {
+            finish_review(&conn, id, review.as_ref())?;
+            match (&review, scored) {
+                (Some(review), Some(scoreboard)) => {
+                    let accuracy = user.fold_wb(review.accuracy.white, review.accuracy.black).accuracy as f32;
+                    set_score_accuracy(&conn, id, accuracy, scoreboard)
+                }
+                _ => Ok(()),
+            }
+        });
+        if let Err(err) = stored {
+            error!("Could not store the review {}: {}", id, err);
+        }
+        review.map(|review| review.accuracy)
+    })
+}
+
+// Route handler `/review/<id>` it returns a post-game review, whose accuracy and moves are missing until it is done.
+#[get("/review/<id>")]
+async fn get_game_review(id: i64, db: &State<DB>) -> Response<Json<ReviewReport>> {
+    let conn = db.get().map_err(|_| (Status::InternalServerError, "Could not read review"))?;
+    let review = get_review(&conn, id).map_err(|_| (Status::InternalServerError, "Could not read review"))?.ok_or((Status::NotFound, "There is no review with this id"))?;
+    let moves = review.moves.and_then(|moves| serde_json::from_str(&moves).ok()).unwrap_or(Value::Array(Vec::new()));
+    let side = |accuracy: Option<f64>, acpl: Option<f64>| Some(SideAccuracy { accuracy: accuracy?, acpl: acpl? });
+    let accuracy = side(review.white_accuracy, review.white_acpl)
+        .zip(side(review.black_accuracy, review.black_acpl))
+        .map(|(white, black)| GameAccuracy { white, black });
+    Ok(Json(ReviewReport {
+        id: review.id,
+        username: review.username,
+        status: review.status,
+        accuracy,
+        moves,
+    }))
 }
 
 // Route handler `/move` it handles the players use and the chess engine's response.
 // It takes a `mov` alias move as form data representing the players move.
 // It uses `CookieJar` to manage session cookies and a `SessionHandler` to manage sessions.
 #[post("/move", data = "<mov>")]
-async fn post_move(mov: String, cookie_jar: &CookieJar<'_>, session_handler: &State<SessionHandler>) -> FenResponse {
+async fn post_move(mov: String, cookie_jar: &CookieJar<'_>, session_handler: &State<SessionHandler>) -> MoveResponse {
     // Grabs the users session if it exists
     let session = find_session(cookie_jar, session_handler).await.ok_or((Status::BadRequest, String::from("You are missing a session key!")))?;
     let mut game = session.get().await;
@@ -100,31 +302,245 @@ async fn post_move(mov: String, cookie_jar: &CookieJar<'_>, session_handler: &St
     let curr_fen = Fen::from_position(game.board.clone(), EnPassantMode::Legal).to_string();
 
     // Applies user's move, if it is invalid, the current fen will be returned
+    // A flag that fell while the user was thinking is replied explicitly
+    if game.board.outcome().is_none() {
+        if let Some(color) = game.clock.as_ref().and_then(|clock| clock.flagged(game.board.turn())) {
+            return Ok(MoveReply::flagged(color, &game.board));
+        }
+    }
+    if game.outcome().is_some() {
+        return Err((Status::NotAcceptable, curr_fen));
+    }
     let uci: Uci = mov.parse().map_err(move |_| (Status::NotAcceptable, curr_fen))?;
     let mov = find_with_auto_promotion(&uci, &game.board).ok_or((Status::BadRequest, String::from("Your move could not be evaluated!")))?;
-    game.board.play_unchecked(&mov);
+    // A move after the flag fell does not count, the game is over and will be resolved by `/game_end`
+    let turn = game.board.turn();
+    if !game.clock.as_mut().is_none_or(|clock| clock.punch(turn)) {
+        return Ok(MoveReply::flagged(turn, &game.board));
+    }
+    game.play(&mov);
 
-    // Generates and applies engine's move
-    let board_clone = game.board.clone();
-    let mov = game.engine.gen_next_move(&board_clone).await.map_err(|_| (Status::InternalServerError, String::from("Could not generate stockfish move")))?;
-    game.board.play_unchecked(&mov);
+    // Generates and applies engine's move, the engine plans its time with the current clock and streams its thinking
+    // The user keeps the turn after an extra move of move odds, while the engine plays its own extra move right after its first one
+    while game.board.turn() != game.user_turn() && game.outcome().is_none() {
+        let board_clone = game.board.clone();
+        let limits = game.search_limits();
+        game.thinking.send(ThinkingEvent::Searching);
+        let mov = game.engine.gen_next_move(&board_clone, &limits).await.map_err(|_| (Status::InternalServerError, String::from("Could not generate stockfish move")))?;
+        let turn = game.board.turn();
+        // The engine lost on time, so its move is not played and the fallen flag is replied instead
+        if !game.clock.as_mut().is_none_or(|clock| clock.punch(turn)) {
+            return Ok(MoveReply::flagged(turn, &game.board));
+        }
+        game.thinking.send(ThinkingEvent::reply(&game.board, &mov));
+        game.play(&mov);
+    }
 
     // Makes duplicate of games fen representation and returns it
     let fen = Fen::from_position(game.board.clone(), EnPassantMode::Legal).to_string();
-    Ok(fen)
+    Ok(MoveReply::Fen(fen))
+}
+
+// Route handler `/thinking` it streams the thinking of the engine in the current game as server-sent events.
+// Every search starts with a `searching` event, followed by the best line of every depth and the played `move`.
+#[get("/thinking")]
+async fn get_thinking(cookie_jar: &CookieJar<'_>, session_handler: &State<SessionHandler>, mut shutdown: Shutdown) -> Response<EventStream![]> {
+    let session = find_session(cookie_jar, session_handler).await.ok_or((Status::BadRequest, "You are missing a session key!"))?;
+    let mut thinking = session.thinking();
+    Ok(EventStream! {
+        loop {
+            let event = select! {
+                event = thinking.recv() => match event {
+                    Ok(event) => event,
+                    // A slow client misses some lines, the next ones are more recent anyway
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                },
+                _ = &mut shutdown => break,
+            };
+            yield Event::json(&event);
+        }
+    })
+}
+
+// Route handler `/tablebase` it returns the Syzygy verdict and the ranked moves of a position, which is sent as fen.
+#[post("/tablebase", data = "<fen>")]
+async fn post_tablebase(fen: String, factory: &State<GeneratorFactory>) -> Response<Json<TablebaseVerdict>> {
+    let tablebase = factory.tablebase().ok_or((Status::NotFound, "There is no tablebase configured"))?;
+    let fen: Fen = fen.trim().parse().map_err(|_| (Status::BadRequest, "Your fen could not be parsed"))?;
+    let board: Chess = fen.into_position(CastlingMode::Standard).map_err(|_| (Status::BadRequest, "Your fen is no legal position"))?;
+    let verdict = probe_tablebase(tablebase.clone(), board).await.map_err(|err| (Status::BadRequest, err))?;
+    Ok(Json(verdict))
+}
+
+// Route handler `/hint` it returns the move the engine suggests for the user in the current game.
+// Every hint is counted and reduces the score of a won game.
+#[post("/hint")]
+async fn post_hint(cookie_jar: &CookieJar<'_>, session_handler: &State<SessionHandler>, factory: &State<GeneratorFactory>) -> Response<Json<Hint>> {
+    // Grabs the users session if it exists
+    let session = find_session(cookie_jar, session_handler).await.ok_or((Status::BadRequest, "You are missing a session key!"))?;
+    let mut game = session.get().await;
+    if game.outcome().is_some() {
+        return Err((Status::BadRequest, "The game is already over"));
+    }
+    if game.board.turn() != game.user_turn() {
+        return Err((Status::BadRequest, "It is not your turn"));
+    }
+
+    let result = factory.search_full_strength(&game.board, 1).await.map_err(|_| (Status::InternalServerError, "Could not generate a hint"))?;
+    let mov = result.best_move.ok_or((Status::InternalServerError, "Could not generate a hint"))?;
+    game.hints_used += 1;
+    Ok(Json(Hint {
+        uci: mov.to_uci(game.board.castles().mode()).to_string(),
+        san: SanPlus::from_move(game.board.clone(), &mov).to_string(),
+        score: result.lines.first().map(|line| line.score),
+        wdl: result.lines.first().map(|line| WdlProbabilities::from(line.estimated_wdl())),
+        hints_used: game.hints_used,
+    }))
 }
 
 // Route handler `/scoreboard` it returns the top <count> scoreboard entries.
-#[get("/scoreboard?<count>")]
-async fn get_scoreboard(count: Option<u16>, db: &State<DB>) -> Response<Json<Vec<ScoreEntry>>> {
+// It takes an optional `variant` query parameter for the scoreboard of `chess960` games or of a variant by its UCI name, e.g. `atomic`.
+#[get("/scoreboard?<count>&<variant>")]
+async fn get_scoreboard(count: Option<u16>, variant: Option<&str>, db: &State<DB>) -> Response<Json<Vec<ScoreEntry>>> {
+    let scoreboard = match variant {
+        None => Scoreboard::Standard,
+        Some(variant) => Scoreboard::new(variant).ok_or((Status::BadRequest, "There is no scoreboard for this variant"))?
+    };
     let conn = db.get().map_err(|_| (Status::InternalServerError, "Could not access database"))?;
     let scores = match count {
-        None => get_all(&conn),
-        Some(count) => get_top(&conn, count)
+        None => get_all(&conn, scoreboard),
+        Some(count) => get_top(&conn, count, scoreboard)
     }.map_err(|_| (Status::InternalServerError, "Could not receive scores!"))?;
     Ok(Json(scores))
 }
 
+// Route handler `/analysis` it analyses the position of the current game at full strength.
+// It takes an optional `lines` query parameter, which is the amount of returned lines.
+#[get("/analysis?<lines>")]
+async fn get_analysis(lines: Option<u32>, cookie_jar: &CookieJar<'_>, session_handler: &State<SessionHandler>, factory: &State<GeneratorFactory>) -> Response<Json<Analysis>> {
+    let lines = analysis_lines(lines)?;
+    // Grabs the users session if it exists, the game is only locked while the board is copied
+    let session = find_session(cookie_jar, session_handler).await.ok_or((Status::BadRequest, "You are missing a session key!"))?;
+    let board = session.get().await.board.clone();
+    let analysis = factory.analyze(&board, lines).await.map_err(|_| (Status::InternalServerError, "Could not analyse the position"))?;
+    Ok(Json(analysis))
+}
+
+// Route handler `/analysis` it analyses an arbitrary position, which is sent as fen, at full strength.
+// It takes an optional `lines` query parameter, which is the amount of returned lines, and an optional `variant` of the position.
+#[post("/analysis?<lines>&<variant>", data = "<fen>")]
+async fn post_analysis(fen: String, lines: Option<u32>, variant: Option<&str>, factory: &State<GeneratorFactory>) -> Response<Json<Analysis>> {
+    let lines = analysis_lines(lines)?;
+    let variant = match variant {
+        Some(name) => Variant::from_uci(name).map_err(|_| (Status::BadRequest, "Your variant submission is invalid"))?,
+        None => Variant::Chess
+    };
+    let fen: Fen = fen.trim().parse().map_err(|_| (Status::BadRequest, "Your fen could not be parsed"))?;
+    let board = VariantPosition::from_setup(variant, fen.into_setup(), CastlingMode::Standard).map_err(|_| (Status::BadRequest, "Your fen is no legal position"))?;
+    let analysis = factory.analyze(&board, lines).await.map_err(|_| (Status::InternalServerError, "Could not analyse the position"))?;
+    Ok(Json(analysis))
+}
+
+// Function to validate the requested amount of analysis lines, three lines are returned if nothing is requested.
+fn analysis_lines(lines: Option<u32>) -> Response<u32> {
+    match lines.unwrap_or(3) {
+        lines @ 1..=5 => Ok(lines),
+        _ => Err((Status::BadRequest, "The amount of lines has to be between 1 and 5"))
+    }
+}
+
+// Route handler `/engine_pool` it returns the queue metrics of the stockfish engine pool.
+// It takes an optional `engine` query parameter for the pool of a registered engine.
+#[get("/engine_pool?<engine>")]
+async fn get_engine_pool(engine: Option<&str>, factory: &State<GeneratorFactory>) -> Response<Json<PoolStats>> {
+    let pool = factory.engine_pool(engine.unwrap_or(DEFAULT_ENGINE)).ok_or((Status::NotFound, "There is no engine pool running"))?;
+    Ok(Json(pool.stats()))
+}
+
+// Route handler `/bots` it returns the configured bots, which can be played instead of a difficulty.
+#[get("/bots")]
+async fn get_bots(factory: &State<GeneratorFactory>) -> Json<Vec<BotEntry>> {
+    Json(factory.bots().iter().map(|bot| BotEntry {
+        name: bot.name.clone(),
+        engine: bot.engine.clone(),
+        elo: bot.elo,
+    }).collect())
+}
+
+// Route handler `/variants` it returns the UCI names of the variants the variant engine is able to play.
+#[get("/variants")]
+async fn get_variants(factory: &State<GeneratorFactory>) -> Json<Vec<&'static str>> {
+    Json(factory.variants().iter().map(|variant| variant.uci()).collect())
+}
+
+// Route handler `/strength` it returns the elo range games can be played at.
+#[get("/strength")]
+async fn get_strength(factory: &State<GeneratorFactory>) -> Response<Json<StrengthRange>> {
+    let range = factory.elo_range().ok_or((Status::NotFound, "The move generator can not limit its strength"))?;
+    Ok(Json(StrengthRange { min: *range.start(), max: *range.end() }))
+}
+
+// Route handler `/exhibition` it starts a game between two bots, which is played on the server.
+// It takes the difficulties of both bots as form data and redirects to the page of the exhibition.
+#[post("/exhibition", data = "<settings>")]
+async fn post_exhibition(settings: Form<ExhibitionSettings>, exhibition_handler: &State<ExhibitionHandler>, factory: &State<GeneratorFactory>) -> Response<Redirect> {
+    // Adaptive bots follow the rating of a user, so they can not play each other
+    let opponent = |value: &str| match value.parse::<i16>() {
+        Ok(level) => DIFFICULTY::new(level).map(Opponent::Difficulty).filter(|opponent| !opponent.is_adaptive()),
+        Err(_) => factory.bot(value).cloned().map(Opponent::Bot),
+    };
+    let white = opponent(&settings.white).ok_or((Status::BadRequest, "Your bot submission is invalid"))?;
+    let black = opponent(&settings.black).ok_or((Status::BadRequest, "Your bot submission is invalid"))?;
+    let id = exhibition_handler.start(factory, white, black).await.map_err(|err| (Status::ServiceUnavailable, err))?;
+    Ok(Redirect::to(uri!(get_spectate(id))))
+}
+
+// Route handler `/exhibitions` it returns the state of every running or recently finished exhibition.
+#[get("/exhibitions")]
+async fn get_exhibitions(exhibition_handler: &State<ExhibitionHandler>) -> Json<Vec<ExhibitionState>> {
+    Json(exhibition_handler.list().await)
+}
+
+// Route handler `/exhibition/<id>/events` it streams the state of an exhibition after every move as server-sent events.
+// The stream ends with the result of the exhibition.
+#[get("/exhibition/<id>/events")]
+async fn get_exhibition_events(id: u64, exhibition_handler: &State<ExhibitionHandler>, mut shutdown: Shutdown) -> Response<EventStream![]> {
+    let mut state = exhibition_handler.get(id).await.ok_or((Status::NotFound, "There is no exhibition with this id"))?;
+    Ok(EventStream! {
+        loop {
+            let current = state.borrow_and_update().clone();
+            let finished = current.result.is_some();
+            yield Event::json(&current);
+            if finished {
+                break;
+            }
+            select! {
+                changed = state.changed() => if changed.is_err() { break },
+                _ = &mut shutdown => break,
+            }
+        }
+    })
+}
+
+// Route handler `/spectate` it renders the read-only board of an exhibition.
+#[get("/spectate?<id>")]
+async fn get_spectate(id: u64, exhibition_handler: &State<ExhibitionHandler>) -> Response<Template> {
+    let state = exhibition_handler.get(id).await.ok_or((Status::NotFound, "There is no exhibition with this id"))?.borrow().clone();
+    Ok(Template::render("spectate", context! {
+        id,
+        white: player_name(&state.white.name, state.white.elo),
+        black: player_name(&state.black.name, state.black.elo),
+    }))
+}
+
+// Function to get the name of a computer player with its elo, which is left out if the engine can not limit its strength.
+fn player_name(name: &str, elo: Option<u16>) -> String {
+    match elo {
+        Some(elo) => format!("{} ({} Elo)", name, elo),
+        None => name.to_string(),
+    }
+}
 
 #[launch]
 fn rocket() -> _ {
@@ -133,11 +549,184 @@ fn rocket() -> _ {
     // Create or open db instance
     let db = DB::new("/db/my.db").expect("Could not open database");
     set_score_schema(&db.get().expect("Could not set schema caused by invalid db")).expect("Error setting schema");
+    set_rating_schema(&db.get().expect("Could not set schema caused by invalid db")).expect("Error setting schema");
+    set_review_schema(&db.get().expect("Could not set schema caused by invalid db")).expect("Error setting schema");
     // Build the rocket application including static file serving, sessions and dynamic html rendering via handlebars
-    rocket::build()
+    let rocket = rocket::build();
+    // Read the move generator backend from the rocket config, stockfish is used if nothing is configured
+    let backend: GeneratorBackend = match rocket.figment().contains("generator") {
+        true => rocket.figment().extract_inner("generator").expect("Invalid move generator configuration"),
+        false => GeneratorBackend::default()
+    };
+    // Read the search limits of every difficulty, e.g. a movetime or a node budget instead of a fixed depth
+    let limits: DifficultyLimits = match rocket.figment().contains("search_limits") {
+        true => rocket.figment().extract_inner("search_limits").expect("Invalid search limits configuration"),
+        false => DifficultyLimits::default()
+    };
+    // Read the amount of pooled stockfish processes, one per available cpu core if nothing is configured
+    let pool_size: usize = match rocket.figment().contains("engine_pool_size") {
+        true => rocket.figment().extract_inner("engine_pool_size").expect("Invalid engine pool size"),
+        false => std::thread::available_parallelism().map_or(4, |cores| cores.get())
+    };
+    // Read the engine binary and its options, invalid values stop the launch before any engine is started
+    // A configured engine has to start, only the default one falls back to the built-in search
+    let engine_config: Option<EngineConfig> = match rocket.figment().contains("engine") {
+        true => Some(rocket.figment().extract_inner("engine").expect("Invalid engine configuration")),
+        false => None
+    };
+    if let Err(err) = engine_config.as_ref().map_or(Ok(()), EngineConfig::validate) {
+        panic!("Invalid engine configuration: {}", err);
+    }
+    // Read the further engines of the registry by their name, which the bots can play with
+    let engines: BTreeMap<String, EngineConfig> = match rocket.figment().contains("engines") {
+        true => rocket.figment().extract_inner("engines").expect("Invalid engine registry configuration"),
+        false => BTreeMap::new()
+    };
+    for (name, engine) in &engines {
+        if let Err(err) = engine.validate() {
+            panic!("Invalid configuration of engine {}: {}", name, err);
+        }
+    }
+    // Read the bots, which play with one of the registered engines
+    let bots: Vec<BotConfig> = match rocket.figment().contains("bots") {
+        true => rocket.figment().extract_inner("bots").expect("Invalid bot configuration"),
+        false => Vec::new()
+    };
+    // Read the opening books of every difficulty, the books are loaded together with the engine pool
+    let books: BookConfig = match rocket.figment().contains("books") {
+        true => rocket.figment().extract_inner("books").expect("Invalid opening book configuration"),
+        false => BookConfig::default()
+    };
+    // Read the directory of the Syzygy tablebase, endgames are not probed if nothing is configured
+    let syzygy_path: Option<PathBuf> = match rocket.figment().contains("syzygy_path") {
+        true => Some(rocket.figment().extract_inner("syzygy_path").expect("Invalid syzygy path")),
+        false => None
+    };
+    // Read the pause before every move of an exhibition in milliseconds, so spectators are able to follow the game
+    let exhibition_move_delay: u64 = match rocket.figment().contains("exhibition_move_delay") {
+        true => rocket.figment().extract_inner("exhibition_move_delay").expect("Invalid exhibition move delay"),
+        false => 1000
+    };
+    // Read the name of the registered engine that plays the variants, variants can not be played if nothing is configured
+    let variant_engine: Option<String> = match rocket.figment().contains("variant_engine") {
+        true => Some(rocket.figment().extract_inner("variant_engine").expect("Invalid variant engine")),
+        false => None
+    };
+    let exhibition_handler = ExhibitionHandler::new(Duration::from_millis(exhibition_move_delay));
+    let generator_config = GeneratorConfig {
+        backend,
+        pool_size,
+        limits,
+        engine: engine_config,
+        engines,
+        bots,
+        variant_engine,
+        books,
+        syzygy_path,
+    };
+    rocket
         .manage(session_handler)
         .manage(db)
-        .mount("/", routes![post_game, post_move, get_game_end, get, get_scoreboard])
+        .manage(exhibition_handler)
+        .mount("/", routes![post_game, post_move, get_game_end, get, get_scoreboard, get_engine_pool, get_analysis, post_analysis, post_hint, post_tablebase, get_strength, get_game_review, get_bots, get_variants, get_thinking, post_exhibition, get_exhibitions, get_exhibition_events, get_spectate])
         .mount("/", FileServer::from(relative!("/static")))
         .attach(Template::fairing())
+        // The engine pool spawns reader tasks and waits for the UCI handshakes, so the factory has to be created inside the async runtime
+        // The launch is aborted if an opening book can not be read or the engine rejects one of the configured options
+        .attach(AdHoc::try_on_ignite("Move Generator", move |rocket| async move {
+            match GeneratorFactory::new(generator_config).await {
+                Ok(factory) => Ok(rocket.manage(factory)),
+                Err(err) => {
+                    error!("Invalid move generator configuration: {}", err);
+                    Err(rocket)
+                }
+            }
+        }))
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::http::ContentType;
+    use rocket::local::asynchronous::Client;
+    use crate::utils::game::SearchLimits;
+    use super::*;
+
+    // Function to build the application with a scripted engine and a database in memory.
+    // Black answers the scholar's mate with the scripted moves and the review searches two plies, so it is done in time.
+    // The scripted engine can not limit its strength, only the bot of it plays at a known elo.
+    async fn client() -> Client {
+        let db = DB::in_memory().unwrap();
+        set_score_schema(&db.get().unwrap()).unwrap();
+        set_review_schema(&db.get().unwrap()).unwrap();
+        set_rating_schema(&db.get().unwrap()).unwrap();
+        let factory = GeneratorFactory::new(GeneratorConfig {
+            backend: GeneratorBackend::Scripted(["e7e5", "b8c6", "g8f6"].map(String::from).to_vec()),
+            pool_size: 1,
+            limits: DifficultyLimits { review: SearchLimits::depth(2), ..DifficultyLimits::default() },
+            engine: None,
+            engines: BTreeMap::new(),
+            bots: vec![BotConfig { name: String::from("Scripted"), engine: String::from(DEFAULT_ENGINE), elo: 1500, limits: SearchLimits::depth(1) }],
+            variant_engine: None,
+            books: BookConfig::default(),
+            syzygy_path: None,
+        }).await.unwrap();
+        let rocket = rocket::build()
+            .manage(SessionHandler::new())
+            .manage(db)
+            .manage(factory)
+            .mount("/", routes![post_game, post_move, get_game_end])
+            .attach(Template::fairing());
+        Client::tracked(rocket).await.unwrap()
+    }
+
+    // Asynchronous function to win a game with the scholar's mate, returns the game-over payload of `/game_end`.
+    async fn scholars_mate(client: &Client, settings: &'static str) -> Value {
+        let response = client.post("/game").header(ContentType::Form).body(settings).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        for mov in ["e2e4", "f1c4", "d1h5", "h5f7"] {
+            assert_eq!(client.post("/move").body(mov).dispatch().await.status(), Status::Ok);
+        }
+        let response = client.get("/game_end").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        response.into_json().await.unwrap()
+    }
+
+    #[rocket::async_test]
+    async fn game_end_contains_accuracy() {
+        let client = client().await;
+        let game_end = scholars_mate(&client, "username=tester&difficulty=1&color=w&bot=Scripted").await;
+        assert_eq!(game_end["winner"], "white");
+        assert_eq!(game_end["ranked"], true);
+        assert_eq!(game_end["score"], 3750.);
+        assert!(game_end["review_id"].is_i64());
+        // Black did not see the mate coming, so it played worse than white
+        let accuracy = &game_end["accuracy"];
+        for side in ["white", "black"] {
+            assert!(accuracy[side]["accuracy"].is_f64(), "{game_end}");
+            assert!(accuracy[side]["acpl"].is_f64(), "{game_end}");
+        }
+        assert!(accuracy["white"]["accuracy"].as_f64() > accuracy["black"]["accuracy"].as_f64(), "{game_end}");
+        // The review is done, so the scoreboard shows the accuracy of the winner as well
+        let conn = client.rocket().state::<DB>().unwrap().get().unwrap();
+        let entries = get_top(&conn, 10, Scoreboard::Standard).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].accuracy.map(f64::from), accuracy["white"]["accuracy"].as_f64());
+    }
+
+    #[rocket::async_test]
+    async fn game_without_elo_is_unranked() {
+        let client = client().await;
+        let game_end = scholars_mate(&client, "username=tester&difficulty=3&color=w").await;
+        assert_eq!(game_end["winner"], "white");
+        assert_eq!(game_end["ranked"], false);
+        assert!(game_end["score"].is_null());
+        assert!(game_end["accuracy"]["white"]["accuracy"].is_f64(), "{game_end}");
+        let entries = get_top(&client.rocket().state::<DB>().unwrap().get().unwrap(), 10, Scoreboard::Standard).unwrap();
+        assert!(entries.is_empty());
+        // An elo can not be requested and the rating of adaptive games could not follow the user
+        for settings in ["username=tester&difficulty=3&color=w&elo=1500", "username=tester&difficulty=4&color=w"] {
+            let response = client.post("/game").header(ContentType::Form).body(settings).dispatch().await;
+            assert_eq!(response.status(), Status::BadRequest, "{settings}");
+        }
+    }
 }
@@ -1,31 +1,93 @@
 use std::env::current_dir;
 use std::sync::{Arc, Mutex, MutexGuard};
 use rusqlite::{Connection, OpenFlags, Statement};
+use rocket::serde::json::serde_json;
 use serde::Serialize;
+use shakmaty::variant::Variant;
 use crate::utils::errors::internal::InternalResult;
-use crate::utils::game::DIFFICULTY;
+use crate::utils::game::{GameReview, WEAKEST_ELO};
 
+// Factor the score is multiplied with for every hint the winner used.
+const HINT_DISCOUNT: f32 = 0.8;
+// Maximum rating change of a game, players with few games change faster so their rating finds their strength quickly.
+const RATING_K: f32 = 32.;
+const PROVISIONAL_RATING_K: f32 = 64.;
+const PROVISIONAL_GAMES: u32 = 5;
 
 #[derive(Serialize)]
 pub struct ScoreEntry {
     pub winner: String,
     pub score: f32,
+    // Accuracy of the winner in the game of the score, `None` until the game is reviewed or if it could not be reviewed.
+    pub accuracy: Option<f32>,
 }
 
 impl ScoreEntry {
-    pub fn new(winner: &String, moves: u32, difficulty: &DIFFICULTY) -> Self {
-        let score = Self::calc_score(moves, difficulty);
+    pub fn new(winner: &str, moves: u32, elo: u16, hints_used: u32, odds_multiplier: f32) -> Self {
+        let score = Self::calc_score(moves, elo, hints_used, odds_multiplier);
         ScoreEntry {
-            winner: winner.clone(),
+            winner: winner.to_string(),
             score,
+            accuracy: None,
         }
     }
-    fn calc_score(moves: u32, difficulty: &DIFFICULTY) -> f32 {
-        let score = (difficulty.parse_elo() as f32)*(10./moves as f32);
-        score.round()
+    // The multiplier of odds games is above 1 if the winner gave the handicap and below 1 if the winner received it
+    fn calc_score(moves: u32, elo: u16, hints_used: u32, odds_multiplier: f32) -> f32 {
+        let score = (elo as f32)*(10./moves as f32);
+        let discount = HINT_DISCOUNT.powi(hints_used as i32);
+        (score*discount*odds_multiplier).round()
     }
 }
 
+// Enum representing the scoreboards, games of every variant are ranked on a scoreboard of their own.
+#[derive(Clone, Copy)]
+pub enum Scoreboard {
+    Standard,
+    Chess960,
+    // A variant besides standard chess.
+    Variant(Variant),
+}
+
+impl Scoreboard {
+    // Method to create a new `Scoreboard` from the name of its variant, which is the UCI name for the variants.
+    pub fn new(variant: &str) -> Option<Self> {
+        match variant {
+            "standard" => Some(Scoreboard::Standard),
+            "chess960" => Some(Scoreboard::Chess960),
+            _ => Variant::from_uci(variant).ok().map(Scoreboard::of)
+        }
+    }
+
+    // Method to get the scoreboard of a variant, standard chess is ranked on the standard scoreboard.
+    pub fn of(variant: Variant) -> Self {
+        match variant {
+            Variant::Chess => Scoreboard::Standard,
+            variant => Scoreboard::Variant(variant),
+        }
+    }
+
+    // Method to get every scoreboard, which all need a table.
+    fn all() -> impl Iterator<Item = Scoreboard> {
+        [Scoreboard::Standard, Scoreboard::Chess960].into_iter().chain(Variant::ALL.into_iter().filter(|variant| *variant != Variant::Chess).map(Scoreboard::Variant))
+    }
+
+    // Method to parse the scoreboard into its database table.
+    fn parse_table(&self) -> &'static str {
+        match self {
+            Scoreboard::Standard | Scoreboard::Variant(Variant::Chess) => "Score",
+            Scoreboard::Chess960 => "Chess960Score",
+            Scoreboard::Variant(Variant::Atomic) => "AtomicScore",
+            Scoreboard::Variant(Variant::Antichess) => "AntichessScore",
+            Scoreboard::Variant(Variant::KingOfTheHill) => "KingOfTheHillScore",
+            Scoreboard::Variant(Variant::ThreeCheck) => "ThreeCheckScore",
+            Scoreboard::Variant(Variant::Crazyhouse) => "CrazyhouseScore",
+            Scoreboard::Variant(Variant::RacingKings) => "RacingKingsScore",
+            Scoreboard::Variant(Variant::Horde) => "HordeScore",
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct DB {
     conn: Arc<Mutex<Connection>>,
 }
@@ -39,36 +101,55 @@ impl DB {
             conn: Arc::new(Mutex::new(conn))
         })
     }
-    pub fn get(&self) -> InternalResult<MutexGuard<Connection>> {
+    // Method to open a database that only lives in memory, so tests do not touch the database of the application.
+    #[cfg(test)]
+    pub fn in_memory() -> InternalResult<Self> {
+        let conn = Connection::open_in_memory().map_err(|_| "DB: Could not open DB Connection")?;
+        Ok(DB {
+            conn: Arc::new(Mutex::new(conn))
+        })
+    }
+    pub fn get(&self) -> InternalResult<MutexGuard<'_, Connection>> {
         let conn = self.conn.lock().map_err(|_| "DB: Could not get database connection")?;
         Ok(conn)
     }
 }
 
 pub fn set_score_schema(conn: &Connection) -> InternalResult<()> {
-    let _ = conn.execute(
-        "CREATE TABLE IF NOT EXISTS Score (
-            winner TEXT PRIMARY KEY,
-            score FLOAT
-        )",
-        (), // empty list of parameters.
-    ).map_err(|_| "DB-SETTING_SCHEMA: Error while creating database table")?;
+    for scoreboard in Scoreboard::all() {
+        let _ = conn.execute(
+            &format!("CREATE TABLE IF NOT EXISTS {} (
+                winner TEXT PRIMARY KEY,
+                score FLOAT,
+                accuracy FLOAT,
+                review_id INTEGER
+            )", scoreboard.parse_table()),
+            (), // empty list of parameters.
+        ).map_err(|_| "DB-SETTING_SCHEMA: Error while creating database table")?;
+    }
+    // Databases of older versions have no accuracy and review yet, the columns already exist otherwise
+    for column in ["accuracy FLOAT", "review_id INTEGER"] {
+        let _ = conn.execute(&format!("ALTER TABLE Score ADD COLUMN {}", column), ());
+    }
     Ok(())
 }
 
-pub fn add_score_entry(conn: &Connection, entry: ScoreEntry) -> InternalResult<()> {
+// Function to add the score of a won game, which only replaces an existing entry of the winner with a higher score.
+// The review of the game identifies the entry its accuracy is added to, once the game is reviewed.
+pub fn add_score_entry(conn: &Connection, entry: ScoreEntry, scoreboard: Scoreboard, review_id: Option<i64>) -> InternalResult<()> {
+    let table = scoreboard.parse_table();
     let res = conn.execute(
-        "INSERT INTO Score (winner, score) VALUES (?1, ?2)",
-        (&entry.winner, &entry.score),
+        &format!("INSERT INTO {} (winner, score, accuracy, review_id) VALUES (?1, ?2, ?3, ?4)", table),
+        (&entry.winner, &entry.score, &entry.accuracy, review_id),
     );
     match res {
         Ok(_) => Ok(()),
         Err(_) => {
-            let old_entry = find_entry(conn, &entry.winner).ok_or("DB-ADDING_SCORE: Could not find database entry")?;
+            let old_entry = find_entry(conn, &entry.winner, scoreboard).ok_or("DB-ADDING_SCORE: Could not find database entry")?;
             if old_entry.score < entry.score {
                 conn.execute(
-                    "UPDATE Score SET score = ?1 WHERE winner = ?2",
-                    (entry.score, entry.winner),
+                    &format!("UPDATE {} SET score = ?1, accuracy = ?2, review_id = ?3 WHERE winner = ?4", table),
+                    (entry.score, entry.accuracy, review_id, entry.winner),
                 ).map_err(|_| "DB-ADDING_SCORE: Could not update database entry")?;
             }
             Ok(())
@@ -76,8 +157,18 @@ pub fn add_score_entry(conn: &Connection, entry: ScoreEntry) -> InternalResult<(
     }
 }
 
-fn find_entry(conn: &Connection, winner: &str) -> Option<ScoreEntry> {
-    let query = "SELECT winner, score FROM Score WHERE winner IS ?1 LIMIT 1";
+// Function to add the accuracy of the winner to the score entry of the reviewed game.
+// The entry is left as it is, if the game did not make it onto the scoreboard or has been replaced by another game of the winner.
+pub fn set_score_accuracy(conn: &Connection, review_id: i64, accuracy: f32, scoreboard: Scoreboard) -> InternalResult<()> {
+    conn.execute(
+        &format!("UPDATE {} SET accuracy = ?1 WHERE review_id = ?2", scoreboard.parse_table()),
+        (accuracy, review_id),
+    ).map_err(|_| "DB-ADDING_SCORE: Could not update accuracy")?;
+    Ok(())
+}
+
+fn find_entry(conn: &Connection, winner: &str, scoreboard: Scoreboard) -> Option<ScoreEntry> {
+    let query = format!("SELECT winner, score, accuracy FROM {} WHERE winner IS ?1 LIMIT 1", scoreboard.parse_table());
     let mut stmt = conn.prepare(&query).ok()?;
 
     let mut score_iter = stmt.query_map([winner], |row| {
@@ -85,14 +176,15 @@ fn find_entry(conn: &Connection, winner: &str) -> Option<ScoreEntry> {
             ScoreEntry {
                 winner: row.get(0)?,
                 score: row.get(1)?,
+                accuracy: row.get(2)?,
             }
         )
     }).ok()?;
     score_iter.next()?.ok()
 }
 
-pub fn get_top(conn: &Connection, count: u16) -> InternalResult<Vec<ScoreEntry>> {
-    let query = format!("SELECT winner, score FROM Score ORDER BY score DESC LIMIT {}", count);
+pub fn get_top(conn: &Connection, count: u16, scoreboard: Scoreboard) -> InternalResult<Vec<ScoreEntry>> {
+    let query = format!("SELECT winner, score, accuracy FROM {} ORDER BY score DESC LIMIT {}", scoreboard.parse_table(), count);
     let stmt = conn.prepare(&query).map_err(|_| "DB-GETTING_TOP: Could not prepare database query")?;
     parse_to_scores(stmt)
 }
@@ -104,6 +196,7 @@ fn parse_to_scores(mut stmt: Statement) -> InternalResult<Vec<ScoreEntry>> {
             ScoreEntry {
                 winner: row.get(0)?,
                 score: row.get(1)?,
+                accuracy: row.get(2)?,
             }
         )
     }).map_err(|_| "DB-GETTING_TOP: Could not create entry list")?;
@@ -115,9 +208,156 @@ fn parse_to_scores(mut stmt: Statement) -> InternalResult<Vec<ScoreEntry>> {
     Ok(entries)
 }
 
-pub fn get_all(conn: &Connection) -> InternalResult<Vec<ScoreEntry>> {
-    let query = "SELECT winner, score FROM Score ORDER BY score DESC".to_string();
+pub fn get_all(conn: &Connection, scoreboard: Scoreboard) -> InternalResult<Vec<ScoreEntry>> {
+    let query = format!("SELECT winner, score, accuracy FROM {} ORDER BY score DESC", scoreboard.parse_table());
     let stmt = conn.prepare(&query).map_err(|_| "DB-GETTING_TOP: Could not prepare database query")?;
 
     parse_to_scores(stmt)
-}
\ No newline at end of file
+}
+
+pub fn set_rating_schema(conn: &Connection) -> InternalResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS Rating (
+            username TEXT PRIMARY KEY,
+            rating INTEGER,
+            games INTEGER
+        )",
+        (),
+    ).map_err(|_| "DB-SETTING_SCHEMA: Error while creating rating table")?;
+    Ok(())
+}
+
+// Function to get the rating and the amount of rated games of a player, `None` if the player has no finished adaptive game.
+pub fn get_rating(conn: &Connection, username: &str) -> InternalResult<Option<(u16, u32)>> {
+    let mut stmt = conn.prepare("SELECT rating, games FROM Rating WHERE username IS ?1 LIMIT 1").map_err(|_| "DB-GETTING_RATING: Could not prepare database query")?;
+    let mut rows = stmt.query_map([username], |row| Ok((row.get(0)?, row.get(1)?))).map_err(|_| "DB-GETTING_RATING: Could not query rating")?;
+    rows.next().transpose().map_err(|_| "DB-GETTING_RATING: Could not read rating")
+}
+
+// Function to adjust the rating of a player after a game against the engine at `engine_elo`, `result` is 1 for a win, 0.5 for a draw and 0 for a loss.
+// Returns the new rating, players without a rating start at `initial`.
+pub fn update_rating(conn: &Connection, username: &str, initial: u16, engine_elo: u16, result: f32) -> InternalResult<u16> {
+    let (rating, games) = get_rating(conn, username)?.unwrap_or((initial, 0));
+    let expected = 1. / (1. + 10f32.powf((engine_elo as f32 - rating as f32) / 400.));
+    let k = if games < PROVISIONAL_GAMES { PROVISIONAL_RATING_K } else { RATING_K };
+    // The rating never falls below the weakest elo a game can be played at
+    let new_rating = (rating as f32 + k * (result - expected)).round().max(WEAKEST_ELO as f32) as u16;
+    conn.execute(
+        "INSERT INTO Rating (username, rating, games) VALUES (?1, ?2, 1)
+            ON CONFLICT(username) DO UPDATE SET rating = ?2, games = games + 1",
+        (username, new_rating),
+    ).map_err(|_| "DB-UPDATING_RATING: Could not update rating")?;
+    Ok(new_rating)
+}
+
+// Structure representing a stored post-game review, the accuracies and the reviewed moves as JSON are stored once the review is done.
+pub struct ReviewEntry {
+    pub id: i64,
+    pub username: String,
+    // `pending`, `done` or `failed`.
+    pub status: String,
+    pub white_accuracy: Option<f64>,
+    pub black_accuracy: Option<f64>,
+    pub white_acpl: Option<f64>,
+    pub black_acpl: Option<f64>,
+    pub moves: Option<String>,
+}
+
+pub fn set_review_schema(conn: &Connection) -> InternalResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS Review (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            username TEXT,
+            status TEXT,
+            white_accuracy FLOAT,
+            black_accuracy FLOAT,
+            white_acpl FLOAT,
+            black_acpl FLOAT,
+            moves TEXT
+        )",
+        (),
+    ).map_err(|_| "DB-SETTING_SCHEMA: Error while creating review table")?;
+    Ok(())
+}
+
+// Function to add a pending review of a finished game, returns its id.
+pub fn add_review(conn: &Connection, username: &str) -> InternalResult<i64> {
+    conn.execute(
+        "INSERT INTO Review (username, status) VALUES (?1, 'pending')",
+        [username],
+    ).map_err(|_| "DB-ADDING_REVIEW: Could not add review")?;
+    Ok(conn.last_insert_rowid())
+}
+
+// Function to store the accuracies and the reviewed moves as JSON, a review without a result has failed.
+pub fn finish_review(conn: &Connection, id: i64, review: Option<&GameReview>) -> InternalResult<()> {
+    let Some(review) = review else {
+        conn.execute("UPDATE Review SET status = 'failed' WHERE id = ?1", [id]).map_err(|_| "DB-FINISHING_REVIEW: Could not update review")?;
+        return Ok(());
+    };
+    let moves = serde_json::to_string(&review.moves).map_err(|_| "DB-FINISHING_REVIEW: Could not serialize review")?;
+    let accuracy = &review.accuracy;
+    conn.execute(
+        "UPDATE Review SET status = 'done', white_accuracy = ?1, black_accuracy = ?2, white_acpl = ?3, black_acpl = ?4, moves = ?5 WHERE id = ?6",
+        (accuracy.white.accuracy, accuracy.black.accuracy, accuracy.white.acpl, accuracy.black.acpl, moves, id),
+    ).map_err(|_| "DB-FINISHING_REVIEW: Could not update review")?;
+    Ok(())
+}
+
+pub fn get_review(conn: &Connection, id: i64) -> InternalResult<Option<ReviewEntry>> {
+    let mut stmt = conn.prepare("SELECT id, username, status, white_accuracy, black_accuracy, white_acpl, black_acpl, moves FROM Review WHERE id = ?1").map_err(|_| "DB-GETTING_REVIEW: Could not prepare database query")?;
+    let mut rows = stmt.query_map([id], |row| {
+        Ok(
+            ReviewEntry {
+                id: row.get(0)?,
+                username: row.get(1)?,
+                status: row.get(2)?,
+                white_accuracy: row.get(3)?,
+                black_accuracy: row.get(4)?,
+                white_acpl: row.get(5)?,
+                black_acpl: row.get(6)?,
+                moves: row.get(7)?,
+            }
+        )
+    }).map_err(|_| "DB-GETTING_REVIEW: Could not query review")?;
+    rows.next().transpose().map_err(|_| "DB-GETTING_REVIEW: Could not read review")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_chess960_scores_apart() {
+        let conn = Connection::open_in_memory().unwrap();
+        set_score_schema(&conn).unwrap();
+        add_score_entry(&conn, ScoreEntry::new("alice", 20, 1500, 0, 1.), Scoreboard::Chess960, None).unwrap();
+        add_score_entry(&conn, ScoreEntry::new("bob", 30, 1500, 0, 1.), Scoreboard::Standard, None).unwrap();
+
+        let chess960 = get_top(&conn, 10, Scoreboard::Chess960).unwrap();
+        let standard = get_top(&conn, 10, Scoreboard::Standard).unwrap();
+        assert_eq!(chess960.iter().map(|entry| entry.winner.as_str()).collect::<Vec<_>>(), ["alice"]);
+        assert_eq!(standard.iter().map(|entry| entry.winner.as_str()).collect::<Vec<_>>(), ["bob"]);
+        assert_eq!(Scoreboard::new("chess960").map(|scoreboard| scoreboard.parse_table()), Some("Chess960Score"));
+    }
+
+    #[test]
+    fn adds_accuracy_to_the_reviewed_game() {
+        let conn = Connection::open_in_memory().unwrap();
+        set_score_schema(&conn).unwrap();
+        let accuracy = |conn: &Connection| find_entry(conn, "alice", Scoreboard::Standard).unwrap().accuracy;
+        // A second game with the same score does not replace the entry, so its review does not touch it either
+        add_score_entry(&conn, ScoreEntry::new("alice", 20, 1500, 0, 1.), Scoreboard::Standard, Some(1)).unwrap();
+        add_score_entry(&conn, ScoreEntry::new("alice", 20, 1500, 0, 1.), Scoreboard::Standard, Some(2)).unwrap();
+        set_score_accuracy(&conn, 2, 50., Scoreboard::Standard).unwrap();
+        assert_eq!(accuracy(&conn), None);
+        set_score_accuracy(&conn, 1, 80., Scoreboard::Standard).unwrap();
+        assert_eq!(accuracy(&conn), Some(80.));
+        // A better game replaces the entry together with its accuracy, a late review of the old game is ignored
+        add_score_entry(&conn, ScoreEntry::new("alice", 10, 1500, 0, 1.), Scoreboard::Standard, Some(3)).unwrap();
+        set_score_accuracy(&conn, 1, 90., Scoreboard::Standard).unwrap();
+        assert_eq!(accuracy(&conn), None);
+        set_score_accuracy(&conn, 3, 70., Scoreboard::Standard).unwrap();
+        assert_eq!(accuracy(&conn), Some(70.));
+    }
+}
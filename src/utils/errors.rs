@@ -4,8 +4,10 @@ pub mod internal {
 
 pub mod external {
     use rocket::http::Status;
+    use rocket::serde::json::Json;
+    use crate::utils::responses::MoveReply;
 
     pub type Response<T> = Result<T, (Status, &'static str)>;
-    pub type FenResponse = Result<String, (Status, String)>;
-    pub type OkOrResponse<T> = Result<Status, (Status, T)>;
+    pub type MoveResponse = Result<MoveReply, (Status, String)>;
+    pub type JsonOrFenResponse<T> = Result<Json<T>, (Status, String)>;
 }
\ No newline at end of file
@@ -0,0 +1,180 @@
+// Importing necessary modules and structures from the `std`, `serde`, `shakmaty` and `tokio` crates.
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use serde::Serialize;
+use shakmaty::{CastlingMode, Chess, EnPassantMode, Outcome, Position};
+use shakmaty::fen::Fen;
+use shakmaty::san::SanPlus;
+use shakmaty::variant::{Variant, VariantPosition};
+use shakmaty::zobrist::{Zobrist64, ZobristHash};
+use tokio::sync::{watch, RwLock};
+
+// Importing the internal result type and the move generators of the game.
+use crate::utils::errors::internal::InternalResult;
+use crate::utils::game::{GeneratorFactory, MoveGenerator, Opponent, SearchLimits};
+
+// Amount of exhibitions that can run at the same time, every exhibition keeps one engine busy at a time.
+const MAX_EXHIBITIONS: usize = 4;
+// Exhibitions are drawn after this many plies, so two bots shuffling their pieces do not play forever.
+const MAX_PLIES: usize = 500;
+// How long a finished exhibition can still be watched.
+const KEEP_FINISHED: Duration = Duration::from_secs(600);
+
+// Structure representing a bot of an exhibition.
+#[derive(Clone, Serialize)]
+pub struct ExhibitionPlayer {
+    pub name: String,
+    // `None` if the engine of the bot can not limit its strength.
+    pub elo: Option<u16>,
+}
+
+// Structure representing the state of an exhibition, which is sent to the spectators after every move.
+#[derive(Clone, Serialize)]
+pub struct ExhibitionState {
+    pub id: u64,
+    pub white: ExhibitionPlayer,
+    pub black: ExhibitionPlayer,
+    pub fen: String,
+    // The moves played so far in SAN notation.
+    pub moves: Vec<String>,
+    // The last move in UCI notation, `None` before the first move.
+    pub last_move: Option<String>,
+    // `1-0`, `0-1`, `1/2-1/2` or `*` if a bot failed, `None` while the exhibition is running.
+    pub result: Option<&'static str>,
+}
+
+// Structure representing a bot while it plays, the move generator and its search limits.
+struct Bot {
+    generator: Box<dyn MoveGenerator>,
+    limits: SearchLimits,
+}
+
+// The `ExhibitionHandler` structure runs the exhibitions and lets spectators follow their state.
+pub struct ExhibitionHandler {
+    // A thread-safe, mutable map of exhibition IDs to the state of the exhibition.
+    exhibitions: Arc<RwLock<HashMap<u64, watch::Receiver<ExhibitionState>>>>,
+    next_id: AtomicU64,
+    // The pause before every move, so spectators are able to follow the game.
+    move_delay: Duration,
+}
+
+impl ExhibitionHandler {
+    // Method to create a new `ExhibitionHandler`.
+    pub fn new(move_delay: Duration) -> Self {
+        ExhibitionHandler {
+            exhibitions: Arc::new(RwLock::new(HashMap::new())),
+            next_id: AtomicU64::new(1),
+            move_delay,
+        }
+    }
+
+    // Asynchronous method to start an exhibition between two bots, which is played by a background task.
+    // Returns the ID of the exhibition.
+    pub async fn start(&self, factory: &GeneratorFactory, white: Opponent, black: Opponent) -> InternalResult<u64> {
+        // The limit is checked and the exhibition is added under the same guard, so concurrent starts can not exceed it
+        let mut exhibitions = self.exhibitions.write().await;
+        let running = exhibitions.values().filter(|state| state.borrow().result.is_none()).count();
+        if running >= MAX_EXHIBITIONS {
+            return Err("EXHIBITION: Too many exhibitions are running");
+        }
+        let (white_player, white_bot) = bot(factory, &white)?;
+        let (black_player, black_bot) = bot(factory, &black)?;
+
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let board = VariantPosition::Chess(Chess::default());
+        let (sender, receiver) = watch::channel(ExhibitionState {
+            id,
+            white: white_player,
+            black: black_player,
+            fen: Fen::from_position(board.clone(), EnPassantMode::Legal).to_string(),
+            moves: Vec::new(),
+            last_move: None,
+            result: None,
+        });
+        exhibitions.insert(id, receiver);
+        drop(exhibitions);
+
+        let exhibitions = self.exhibitions.clone();
+        let move_delay = self.move_delay;
+        tokio::spawn(async move {
+            run(board, [white_bot, black_bot], &sender, move_delay).await;
+            // Spectators still see the result for a while
+            tokio::time::sleep(KEEP_FINISHED).await;
+            exhibitions.write().await.remove(&id);
+        });
+        Ok(id)
+    }
+
+    // Asynchronous method to get the state of an exhibition by its ID, which is updated after every move.
+    pub async fn get(&self, id: u64) -> Option<watch::Receiver<ExhibitionState>> {
+        self.exhibitions.read().await.get(&id).cloned()
+    }
+
+    // Asynchronous method to get the current state of every exhibition, ordered by their ID.
+    pub async fn list(&self) -> Vec<ExhibitionState> {
+        let mut states: Vec<ExhibitionState> = self.exhibitions.read().await.values().map(|state| state.borrow().clone()).collect();
+        states.sort_by_key(|state| state.id);
+        states
+    }
+}
+
+// Function to create the bot of an opponent, a difficulty plays at its own elo and a configured bot at the configured one.
+// Exhibition bots are built without pondering, two pondering bots would keep two engines of the pool busy.
+fn bot(factory: &GeneratorFactory, opponent: &Opponent) -> InternalResult<(ExhibitionPlayer, Bot)> {
+    let elo = match opponent {
+        Opponent::Difficulty(difficulty) => factory.strength(None, difficulty.parse_elo())?,
+        Opponent::Bot(bot) => Some(bot.elo),
+    };
+    let (generator, limits) = opponent.build(elo, Variant::Chess, false, factory).ok_or("EXHIBITION: The bot could not be created")?;
+    let player = ExhibitionPlayer { name: opponent.name().to_string(), elo };
+    Ok((player, Bot { generator, limits }))
+}
+
+// Asynchronous function to play an exhibition until it ends, every move is sent to the spectators.
+async fn run(mut board: VariantPosition, mut bots: [Bot; 2], state: &watch::Sender<ExhibitionState>, move_delay: Duration) {
+    // How often every position occurred, by its zobrist hash
+    let mut positions: HashMap<Zobrist64, u32> = HashMap::new();
+    loop {
+        let plies = state.borrow().moves.len();
+        let repetitions = positions.entry(board.zobrist_hash(EnPassantMode::Legal)).or_insert(0);
+        *repetitions += 1;
+        if let Some(result) = result(&board, plies, *repetitions) {
+            state.send_modify(|state| state.result = Some(result));
+            return;
+        }
+        tokio::time::sleep(move_delay).await;
+
+        let bot = &mut bots[board.turn().fold_wb(0, 1)];
+        let mov = match bot.generator.gen_next_move(&board, &bot.limits).await {
+            Ok(mov) => mov,
+            Err(err) => {
+                warn!("EXHIBITION: The game was aborted: {}", err);
+                state.send_modify(|state| state.result = Some("*"));
+                return;
+            }
+        };
+        let san = SanPlus::from_move_and_play_unchecked(&mut board, &mov).to_string();
+        let fen = Fen::from_position(board.clone(), EnPassantMode::Legal).to_string();
+        state.send_modify(|state| {
+            state.fen = fen;
+            state.moves.push(san);
+            state.last_move = Some(mov.to_uci(CastlingMode::Standard).to_string());
+        });
+    }
+}
+
+// Function to get the result of an exhibition, `None` if it is still running.
+// Besides the board itself, the fifty-move rule, threefold repetition and the maximum amount of plies end the game.
+fn result(board: &VariantPosition, plies: usize, repetitions: u32) -> Option<&'static str> {
+    let outcome = match board.outcome() {
+        Some(outcome) => outcome,
+        None if board.halfmoves() >= 100 || repetitions >= 3 || plies >= MAX_PLIES => Outcome::Draw,
+        None => return None,
+    };
+    Some(match outcome {
+        Outcome::Decisive { winner } => winner.fold_wb("1-0", "0-1"),
+        Outcome::Draw => "1/2-1/2",
+    })
+}
@@ -1,20 +1,54 @@
+// The `analysis` module contains the JSON representation of a position analysis.
+mod analysis;
+// The `book` module contains the Polyglot opening books.
+mod book;
 // The `engine` module contains the logic for the chess engine.
 mod engine;
+// The `generator` module contains the `MoveGenerator` trait and its configurable backends.
+mod generator;
+// The `pool` module contains the pool of stockfish processes shared by all sessions.
+mod pool;
+// The `review` module contains the post-game review.
+mod review;
+// The `search` module contains the built-in alpha-beta engine.
+mod search;
+// The `tablebase` module contains the Syzygy endgame tablebase.
+mod tablebase;
+// The `thinking` module contains the live stream of the engine thinking.
+mod thinking;
+// The `weakening` module contains the weakening layer for games below the weakest elo of the engine.
+mod weakening;
 
-// Importing necessary modules and structures from the `rand` and `shakmaty` crates.
-use shakmaty::{Chess, Move, Position};
+// Importing necessary modules and structures from the `std`, `rand` and `shakmaty` crates.
+use std::time::Instant;
+use shakmaty::{CastlingMode, Chess, Color, File, Move, Outcome, Position, PositionErrorKinds, Rank, Role, Setup, Square};
+use rand::Rng;
 use rand::seq::SliceRandom;
+use shakmaty::fen::Fen;
 use shakmaty::uci::Uci;
+use shakmaty::variant::{Variant, VariantPosition};
+use crate::utils::errors::internal::InternalResult;
 
-// Importing the `Engine` structure from the `engine` module.
-use engine::Engine;
+// Re-exporting the move generator abstraction from the `generator` module.
+pub use analysis::{Analysis, WdlProbabilities};
+pub use book::BookConfig;
+pub use engine::EngineConfig;
+pub use generator::{BotConfig, ClockState, DEFAULT_ENGINE, DifficultyLimits, GeneratorBackend, GeneratorConfig, GeneratorFactory, MoveGenerator, Score, SearchLimits};
+pub use pool::PoolStats;
+pub use review::{review_game, GameAccuracy, GameReview, SideAccuracy};
+pub use thinking::{ThinkingEvent, ThinkingStream};
+pub use tablebase::{probe as probe_tablebase, TablebaseVerdict};
+pub use weakening::WEAKEST_ELO;
 
 // Enum representing the difficulty levels of the game.
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Clone)]
 pub enum DIFFICULTY {
     EASY,
     MEDIUM,
     HARD,
+    // The elo follows the rating of the player, which is adjusted after every finished game.
+    ADAPTIVE,
 }
 
 impl DIFFICULTY {
@@ -23,15 +57,19 @@ impl DIFFICULTY {
         match self {
             DIFFICULTY::EASY => 1,
             DIFFICULTY::MEDIUM => 3,
-            DIFFICULTY::HARD => 10
+            DIFFICULTY::HARD => 10,
+            DIFFICULTY::ADAPTIVE => 10
         }
     }
 
+    // Method to parse the difficulty level into the elo it is played at, if no elo is chosen.
     pub fn parse_elo(&self) -> u16 {
         match self {
             DIFFICULTY::EASY => 400,
             DIFFICULTY::MEDIUM => 900,
-            DIFFICULTY::HARD => 3500
+            DIFFICULTY::HARD => 3500,
+            // The rating of players without a finished adaptive game
+            DIFFICULTY::ADAPTIVE => 800
         }
     }
 
@@ -41,6 +79,7 @@ impl DIFFICULTY {
             1 => Some(DIFFICULTY::EASY),
             2 => Some(DIFFICULTY::MEDIUM),
             3 => Some(DIFFICULTY::HARD),
+            4 => Some(DIFFICULTY::ADAPTIVE),
             _ => None
         }
     }
@@ -50,12 +89,278 @@ impl DIFFICULTY {
         match self {
             DIFFICULTY::EASY => "Martin",
             DIFFICULTY::MEDIUM => "Maggus Reischl",
-            DIFFICULTY::HARD => "Maggus Carlsen"
+            DIFFICULTY::HARD => "Maggus Carlsen",
+            DIFFICULTY::ADAPTIVE => "Maggus Chameleon"
         }
     }
 }
 
+// Enum representing what one side gives the other at the start of an odds game.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Handicap {
+    Knight,
+    Rook,
+    Queen,
+    // The other side plays two moves in a row at the start.
+    Move,
+}
+
+impl Handicap {
+    // Method to create a new `Handicap` from its name.
+    pub fn new(name: &str) -> Option<Self> {
+        match name {
+            "knight" => Some(Handicap::Knight),
+            "rook" => Some(Handicap::Rook),
+            "queen" => Some(Handicap::Queen),
+            "move" => Some(Handicap::Move),
+            _ => None
+        }
+    }
+
+    // Method to parse the handicap into its value in pawns.
+    pub fn parse_value(&self) -> f32 {
+        match self {
+            Handicap::Knight => 3.,
+            Handicap::Rook => 5.,
+            Handicap::Queen => 9.,
+            Handicap::Move => 1.,
+        }
+    }
+
+    // Method to parse the handicap into the square of the missing piece of white, the piece of black is mirrored.
+    fn parse_square(&self) -> Option<Square> {
+        match self {
+            Handicap::Knight => Some(Square::B1),
+            Handicap::Rook => Some(Square::A1),
+            Handicap::Queen => Some(Square::D1),
+            Handicap::Move => None,
+        }
+    }
+}
+
+// Structure representing the odds of a game, the handicap and the side that gives it.
+#[derive(Clone, Copy)]
+pub struct Odds {
+    pub handicap: Handicap,
+    pub giver: Color,
+}
+
+impl Odds {
+    // Method to build the start position, the piece of the handicap is missing on the side that gives it.
+    pub fn start_position(&self) -> Option<Chess> {
+        let Some(square) = self.handicap.parse_square() else {
+            return Some(Chess::default());
+        };
+        let square = self.giver.fold_wb(square, square.flip_vertical());
+        let mut setup = Setup::initial();
+        setup.board.discard_piece_at(square);
+        // A missing rook can not castle anymore
+        setup.castling_rights.discard(square);
+        setup.position(CastlingMode::Standard).ok()
+    }
+
+    // Method to get the side that plays an extra move at the start, `None` if the handicap is a piece.
+    pub fn extra_move(&self) -> Option<Color> {
+        (self.handicap == Handicap::Move).then_some(!self.giver)
+    }
+
+    // Method to get the multiplier of the score of the user, giving a handicap raises the score and receiving one lowers it.
+    pub fn score_multiplier(&self, user: Color) -> f32 {
+        let factor = 1. + self.handicap.parse_value() / 10.;
+        if self.giver == user { factor } else { 1. / factor }
+    }
+}
+
+// Amount of Chess960 start positions, the position with the index 518 is the standard one.
+const CHESS960_POSITIONS: u16 = 960;
+// The squares of both knights of a Chess960 start position among the five squares left by the bishops and the queen.
+const CHESS960_KNIGHTS: [(usize, usize); 10] = [(0, 1), (0, 2), (0, 3), (0, 4), (1, 2), (1, 3), (1, 4), (2, 3), (2, 4), (3, 4)];
+
+// The reasons a custom position is rejected for, the first one the position violates is reported.
+const POSITION_ERRORS: [(PositionErrorKinds, &str); 8] = [
+    (PositionErrorKinds::EMPTY_BOARD, "Your FEN has no pieces on the board"),
+    (PositionErrorKinds::MISSING_KING.union(PositionErrorKinds::TOO_MANY_KINGS), "Your FEN needs exactly one king per side"),
+    (PositionErrorKinds::PAWNS_ON_BACKRANK, "Your FEN has pawns on the first or last rank"),
+    // The reason is narrowed down by `castling_error`
+    (PositionErrorKinds::INVALID_CASTLING_RIGHTS, "The castling rights of your FEN do not match the kings and rooks on the board"),
+    (PositionErrorKinds::INVALID_EP_SQUARE, "The en passant square of your FEN is invalid"),
+    (PositionErrorKinds::OPPOSITE_CHECK, "In your FEN the side that is not to move is in check"),
+    (PositionErrorKinds::IMPOSSIBLE_CHECK, "The checks of your FEN are impossible"),
+    (PositionErrorKinds::TOO_MUCH_MATERIAL, "Your FEN has more pieces than a game can reach"),
+];
+
+// Enum representing the position a game starts from.
+#[derive(Clone)]
+pub enum StartPosition {
+    Standard,
+    Odds(Odds),
+    // A Chess960 start position by its index.
+    Chess960(u16),
+    // The start position of a variant, which is played against the variant engine.
+    Variant(Variant),
+    // A custom position by its FEN, these games are unranked.
+    Fen(Fen),
+}
+
+impl StartPosition {
+    // Method to create a Chess960 start position by its index, a random one is chosen if there is no index.
+    pub fn chess960(index: Option<u16>) -> Option<Self> {
+        let index = index.unwrap_or_else(|| rand::thread_rng().gen_range(0..CHESS960_POSITIONS));
+        (index < CHESS960_POSITIONS).then_some(StartPosition::Chess960(index))
+    }
+
+    // Method to build the board the game starts with, a custom position is rejected with the reason it is illegal for.
+    pub fn board(&self) -> InternalResult<VariantPosition> {
+        match self {
+            StartPosition::Standard => Ok(VariantPosition::Chess(Chess::default())),
+            StartPosition::Odds(odds) => odds.start_position().map(VariantPosition::Chess).ok_or("The odds can not be given"),
+            StartPosition::Chess960(index) => chess960_position(*index).map(VariantPosition::Chess).ok_or("Your Chess960 position submission is invalid"),
+            StartPosition::Variant(variant) => Ok(VariantPosition::new(*variant)),
+            StartPosition::Fen(fen) => custom_position(fen),
+        }
+    }
+
+    // Method to get the odds of the game, `None` if the game is played without odds.
+    pub fn odds(&self) -> Option<Odds> {
+        match self {
+            StartPosition::Odds(odds) => Some(*odds),
+            _ => None
+        }
+    }
+
+    // Method to check if the game is a Chess960 game.
+    pub fn is_chess960(&self) -> bool {
+        matches!(self, StartPosition::Chess960(_))
+    }
+
+    // Method to check if the start position is ranked, games from custom positions could farm the scoreboard.
+    pub fn is_ranked(&self) -> bool {
+        !matches!(self, StartPosition::Fen(_))
+    }
+
+    // Method to get the variant of the game, odds, Chess960 and custom games are standard chess.
+    pub fn variant(&self) -> Variant {
+        match self {
+            StartPosition::Variant(variant) => *variant,
+            _ => Variant::Chess
+        }
+    }
+}
+
+// Function to build a custom position out of its FEN.
+// Castling rights of rooks besides the corners are castled like in Chess960.
+fn custom_position(fen: &Fen) -> InternalResult<VariantPosition> {
+    let setup = fen.as_setup().clone();
+    let mode = CastlingMode::detect(&setup);
+    VariantPosition::from_setup(Variant::Chess, setup, mode).map_err(|err| {
+        let kinds = err.kinds();
+        match POSITION_ERRORS.iter().find(|(kind, _)| kinds.intersects(*kind)) {
+            Some((PositionErrorKinds::INVALID_CASTLING_RIGHTS, reason)) => castling_error(fen.as_setup()).unwrap_or(reason),
+            Some((_, reason)) => reason,
+            None => "Your FEN is not a legal position",
+        }
+    })
+}
+
+// Function to get the reason the castling rights of a custom position are invalid for, which shakmaty does not tell.
+// A castling right needs a rook of its color on its square and a king on the back rank, with at most one right on each side of the king.
+fn castling_error(setup: &Setup) -> Option<&'static str> {
+    for color in Color::ALL {
+        let rights = setup.castling_rights & color.backrank();
+        if rights.is_empty() {
+            continue;
+        }
+        if (rights & !(setup.board.rooks() & setup.board.by_color(color))).any() {
+            return Some("Your FEN has a castling right without a rook on the corner");
+        }
+        let king = setup.board.king_of(color)?;
+        if king.rank() != color.backrank() {
+            return Some("Your FEN has castling rights, but the king is not on its start square");
+        }
+        let queen_side = rights.into_iter().filter(|rook| rook.file() < king.file()).count();
+        if queen_side > 1 || rights.count() - queen_side > 1 {
+            return Some("Your FEN has two castling rights on the same side of the king");
+        }
+    }
+    None
+}
+
+// Function to build a Chess960 start position out of its index, the pieces are placed like in the Scharnagl numbering.
+fn chess960_position(index: u16) -> Option<Chess> {
+    let mut back_rank: [Option<Role>; 8] = [None; 8];
+    let mut n = usize::from(index);
+    // One bishop stands on the light squares b, d, f or h and the other one on the dark squares a, c, e or g
+    back_rank[n % 4 * 2 + 1] = Some(Role::Bishop);
+    n /= 4;
+    back_rank[n % 4 * 2] = Some(Role::Bishop);
+    n /= 4;
+    // The queen and the knights are placed on the empty squares counted from the a-file
+    place_chess960_piece(&mut back_rank, n % 6, Role::Queen);
+    let (first, second) = CHESS960_KNIGHTS.get(n / 6)?;
+    place_chess960_piece(&mut back_rank, *second, Role::Knight);
+    place_chess960_piece(&mut back_rank, *first, Role::Knight);
+    // The king stands between the rooks on the last three empty squares
+    for role in [Role::Rook, Role::King, Role::Rook] {
+        place_chess960_piece(&mut back_rank, 0, role);
+    }
+
+    let mut setup = Setup::empty();
+    for (file, role) in back_rank.into_iter().enumerate() {
+        let file = File::new(file as u32);
+        let role = role?;
+        setup.board.set_piece_at(Square::from_coords(file, Rank::First), role.of(Color::White));
+        setup.board.set_piece_at(Square::from_coords(file, Rank::Second), Role::Pawn.of(Color::White));
+        setup.board.set_piece_at(Square::from_coords(file, Rank::Seventh), Role::Pawn.of(Color::Black));
+        setup.board.set_piece_at(Square::from_coords(file, Rank::Eighth), role.of(Color::Black));
+    }
+    // Both sides are able to castle with both rooks
+    setup.castling_rights = setup.board.rooks();
+    setup.position(CastlingMode::Chess960).ok()
+}
+
+// Function to place a piece on the `nth` empty square of the back rank.
+fn place_chess960_piece(back_rank: &mut [Option<Role>; 8], nth: usize, role: Role) {
+    if let Some(square) = back_rank.iter_mut().filter(|square| square.is_none()).nth(nth) {
+        *square = Some(role);
+    }
+}
+
+// Enum representing the computer opponent of a game, one of the difficulties or a configured bot.
+#[derive(Clone)]
+pub enum Opponent {
+    Difficulty(DIFFICULTY),
+    Bot(BotConfig),
+}
+
+impl Opponent {
+    // Method to get the name of the opponent.
+    pub fn name(&self) -> &str {
+        match self {
+            Opponent::Difficulty(difficulty) => difficulty.parse_player_name(),
+            Opponent::Bot(bot) => &bot.name,
+        }
+    }
+
+    // Method to build the move generator of the opponent and its search limits, `elo` is only used by the difficulties.
+    // Without an elo the engine plays at full strength within the limits of the difficulty.
+    // Games of a variant are played by the variant engine at the limits of the difficulty.
+    // With `ponder` the engine may think on the turn of the opponent, if pondering is configured.
+    pub fn build(&self, elo: Option<u16>, variant: Variant, ponder: bool, factory: &GeneratorFactory) -> Option<(Box<dyn MoveGenerator>, SearchLimits)> {
+        match self {
+            Opponent::Difficulty(difficulty) if variant != Variant::Chess => Some((factory.build_variant(elo, ponder)?, factory.limits(difficulty))),
+            Opponent::Difficulty(difficulty) => Some((factory.build(difficulty, elo, ponder)?, factory.limits(difficulty))),
+            Opponent::Bot(bot) => Some((factory.build_bot(bot, ponder)?, bot.limits.clone())),
+        }
+    }
+
+    // Method to check if the elo of the opponent follows the rating of the user.
+    pub fn is_adaptive(&self) -> bool {
+        matches!(self, Opponent::Difficulty(DIFFICULTY::ADAPTIVE))
+    }
+}
+
 // Enum representing the color of the player.
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Clone)]
 pub enum COLOR {
     BLACK,
@@ -77,6 +382,14 @@ impl COLOR {
         }
     }
 
+    // Method to parse the color into the color of the board.
+    pub fn parse_color(&self) -> Color {
+        match &self {
+            COLOR::BLACK => Color::Black,
+            COLOR::WHITE => Color::White
+        }
+    }
+
     // Method to parse the color into a character code.
     pub fn parse_code(&self) -> char {
         match &self {
@@ -86,32 +399,197 @@ impl COLOR {
     }
 }
 
+// Structure representing the chess clock of a game, the time of the side to move runs since `turn_started`.
+pub struct GameClock {
+    white_ms: u64,
+    black_ms: u64,
+    increment_ms: u64,
+    turn_started: Instant,
+    flagged: Option<Color>,
+}
+
+impl GameClock {
+    // Method to create a new `GameClock` with the same time and increment for both sides.
+    pub fn new(minutes: u16, increment_secs: u16) -> Self {
+        let time_ms = u64::from(minutes) * 60_000;
+        GameClock {
+            white_ms: time_ms,
+            black_ms: time_ms,
+            increment_ms: u64::from(increment_secs) * 1000,
+            turn_started: Instant::now(),
+            flagged: None,
+        }
+    }
+
+    // Method to stop the clock of the side that just moved and start the clock of the other side.
+    // Returns `false` if the side ran out of time before it moved.
+    pub fn punch(&mut self, color: Color) -> bool {
+        let elapsed = self.turn_started.elapsed().as_millis() as u64;
+        self.turn_started = Instant::now();
+        let increment_ms = self.increment_ms;
+        let remaining = color.fold_wb(&mut self.white_ms, &mut self.black_ms);
+        if elapsed >= *remaining {
+            *remaining = 0;
+            self.flagged = Some(color);
+            return false;
+        }
+        *remaining = *remaining - elapsed + increment_ms;
+        true
+    }
+
+    // Method to get the side that ran out of time, the running time of the side to move is taken into account.
+    pub fn flagged(&self, turn: Color) -> Option<Color> {
+        let remaining = turn.fold_wb(self.white_ms, self.black_ms);
+        match self.flagged {
+            Some(color) => Some(color),
+            None if self.turn_started.elapsed().as_millis() as u64 >= remaining => Some(turn),
+            None => None
+        }
+    }
+
+    // Method to get a snapshot of the clock, which can be passed to the move generator.
+    pub fn state(&self) -> ClockState {
+        ClockState {
+            white_ms: self.white_ms,
+            black_ms: self.black_ms,
+            white_inc_ms: self.increment_ms,
+            black_inc_ms: self.increment_ms,
+        }
+    }
+}
+
+// Enum representing why a game could not be created.
+#[derive(Debug)]
+pub enum GameError {
+    // The start position can not be played, with the reason for the user.
+    Position(&'static str),
+    // The engine could not be built or could not play its opening move.
+    Engine,
+    // The engine ran out of time on the clock of the game before its opening move was played.
+    Flagged,
+}
+
 // Structure representing a game of chess.
 pub struct Game {
-    pub board: Chess,
-    pub engine: Engine,
-    pub difficulty: DIFFICULTY,
+    pub board: VariantPosition,
+    // The position the game started from and every move played since, which are needed to review the game.
+    pub start: VariantPosition,
+    pub moves: Vec<Move>,
+    pub engine: Box<dyn MoveGenerator>,
+    pub opponent: Opponent,
+    // The elo the engine plays at, which is the base of the score.
+    // `None` if the engine can not limit its strength, the game is unranked then.
+    pub elo: Option<u16>,
     pub username: String,
     pub user_color: char,
+    // The search limits of the engine, taken from the difficulty or the bot.
+    pub limits: SearchLimits,
+    // `None` if the game is played without a clock.
+    pub clock: Option<GameClock>,
+    // Streams what the engine is thinking while it searches its reply.
+    pub thinking: ThinkingStream,
+    // The amount of hints the user asked for, every hint reduces the score.
+    pub hints_used: u32,
+    // The position the game started from before the first move, e.g. with odds, as a Chess960 game or from a FEN.
+    pub start_position: StartPosition,
+    // The side that still plays its extra move of move odds.
+    pub extra_move: Option<Color>,
 }
 
 impl Game {
     // Asynchronous method to create a new `Game`.
-    pub async fn new(user_color: COLOR, difficulty: DIFFICULTY, username: String) -> Option<Self> {
-        let mut board = Chess::default();
-        let engine = Engine::new(difficulty.parse_depth(), difficulty.parse_elo())?;
-        if matches!(user_color, COLOR::BLACK) {
-            let uci: Uci = "d2d4".parse().ok()?;
-            let mov: Move = uci.to_move(&board).ok()?;
-            board.play_unchecked(&mov);
-        };
-        Some(Game {
+    // The start position is validated, so an illegal custom position is reported with its reason.
+    pub async fn new(user_color: COLOR, opponent: Opponent, elo: Option<u16>, username: String, clock: Option<GameClock>, start_position: StartPosition, factory: &GeneratorFactory) -> Result<Self, GameError> {
+        let board = start_position.board().map_err(GameError::Position)?;
+        // The user plays the first move of a custom position, which has to be still running
+        if let StartPosition::Fen(_) = start_position {
+            if board.turn() != user_color.parse_color() {
+                return Err(GameError::Position("The side to move of your FEN does not match your color"));
+            }
+            if board.outcome().is_some() {
+                return Err(GameError::Position("The game of your FEN is already over"));
+            }
+        }
+        let (engine, limits) = opponent.build(elo, start_position.variant(), true, factory).ok_or(GameError::Engine)?;
+        let mut game = Game {
+            start: board.clone(),
             board,
+            moves: Vec::new(),
             engine,
-            difficulty,
+            opponent,
+            elo,
             username,
             user_color: user_color.parse_code(),
-        })
+            limits,
+            clock,
+            thinking: ThinkingStream::new(),
+            hints_used: 0,
+            extra_move: start_position.odds().and_then(|odds| odds.extra_move()),
+            start_position,
+        };
+        // The engine opens the game if the user plays black, usually out of its opening book
+        // With move odds for the engine, it plays its extra move right away as well
+        while game.board.turn() != game.user_turn() && game.outcome().is_none() {
+            let limits = game.search_limits();
+            let mov = game.engine.gen_next_move(&game.board, &limits).await.map_err(|_| GameError::Engine)?;
+            if let Some(clock) = game.clock.as_mut() {
+                if !clock.punch(game.board.turn()) {
+                    return Err(GameError::Flagged);
+                }
+            }
+            game.play(&mov);
+        };
+        Ok(game)
+    }
+
+    // Method to play a move on the board and record it for the review.
+    // The extra move of move odds is followed by a pass, so the game starts over after it and it is not reviewed.
+    pub fn play(&mut self, mov: &Move) {
+        let turn = self.board.turn();
+        self.board.play_unchecked(mov);
+        self.moves.push(mov.clone());
+        if self.extra_move != Some(turn) {
+            return;
+        }
+        self.extra_move = None;
+        // Passing is not possible while the other side is in check, so a checking extra move is not followed by a pass
+        if let Ok(board) = self.board.clone().swap_turn() {
+            self.board = board;
+        }
+        self.start = self.board.clone();
+        self.moves.clear();
+    }
+
+    // Method to check if the game is ranked, the score needs the elo of the engine and a start position that can not farm the scoreboard.
+    pub fn is_ranked(&self) -> bool {
+        self.elo.is_some() && self.start_position.is_ranked()
+    }
+
+    // Method to get the color of the user.
+    pub fn user_turn(&self) -> Color {
+        if self.user_color == 'w' { Color::White } else { Color::Black }
+    }
+
+    // Method to get the search limits for the next engine move, including the current clock and the thinking stream.
+    pub fn search_limits(&self) -> SearchLimits {
+        self.limits.clone()
+            .with_clock(self.clock.as_ref().map(GameClock::state))
+            .with_thinking(self.thinking.clone())
+    }
+
+    // Method to get the outcome of the game, `None` if the game is still running.
+    // Besides the board itself, a fallen flag and the fifty-move rule end the game.
+    pub fn outcome(&self) -> Option<Outcome> {
+        if let Some(outcome) = self.board.outcome() {
+            return Some(outcome);
+        }
+        if let Some(color) = self.clock.as_ref().and_then(|clock| clock.flagged(self.board.turn())) {
+            return Some(Outcome::Decisive { winner: !color });
+        }
+        if self.board.halfmoves() >= 100 {
+            return Some(Outcome::Draw);
+        }
+        None
     }
 }
 
@@ -126,7 +604,7 @@ impl Game {
 /// # Returns
 ///
 /// * `Option<Move>` - The found move, or `None` if no matching move or promotion move could be found.
-pub fn find_with_auto_promotion(uci: &Uci, board: &Chess) -> Option<Move> {
+pub fn find_with_auto_promotion(uci: &Uci, board: &VariantPosition) -> Option<Move> {
     // Try to convert the UCI command to a move on the given chess board.
     match uci.to_move(board) {
         // If the UCI command represents a valid move, return the move.
@@ -144,3 +622,69 @@ pub fn find_with_auto_promotion(uci: &Uci, board: &Chess) -> Option<Move> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chess960_518_is_standard() {
+        let position = chess960_position(518).expect("518 is a valid index");
+        assert_eq!(position.board().to_string(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR");
+        assert_eq!(position.board(), Chess::default().board());
+    }
+
+    #[test]
+    fn chess960_positions_are_legal() {
+        let mut back_ranks = std::collections::HashSet::new();
+        for index in 0..CHESS960_POSITIONS {
+            let position = chess960_position(index).unwrap_or_else(|| panic!("{index} has no position"));
+            let board = position.board();
+            let white = board.by_color(Color::White) & Rank::First;
+            let bishops: Vec<Square> = (white & board.bishops()).into_iter().collect();
+            assert_eq!(bishops.len(), 2, "{index} has not two bishops");
+            assert_ne!(bishops[0].is_light(), bishops[1].is_light(), "{index} has its bishops on the same color");
+            let rooks: Vec<Square> = (white & board.rooks()).into_iter().collect();
+            let king = board.king_of(Color::White).expect("every position has a king");
+            assert!(rooks[0].file() < king.file() && king.file() < rooks[1].file(), "{index} has its king outside the rooks");
+            assert!(back_ranks.insert(board.to_string()), "{index} repeats a position");
+        }
+    }
+
+    // Function to get the reason a custom position is rejected for.
+    fn custom_error(fen: &str) -> Option<&'static str> {
+        custom_position(&fen.parse().unwrap()).err()
+    }
+
+    #[test]
+    fn rejects_castling_right_without_rook() {
+        assert_eq!(custom_error("r3k2r/8/8/8/8/8/8/4K2R w KQkq - 0 1"), Some("Your FEN has a castling right without a rook on the corner"));
+        // A rook of the other color does not count
+        assert_eq!(custom_error("r3k2r/8/8/8/8/8/8/r3K2R w KQkq - 0 1"), Some("Your FEN has a castling right without a rook on the corner"));
+    }
+
+    #[test]
+    fn rejects_castling_right_of_moved_king() {
+        assert_eq!(custom_error("r3k2r/8/8/8/8/8/4K3/R6R w KQkq - 0 1"), Some("Your FEN has castling rights, but the king is not on its start square"));
+    }
+
+    #[test]
+    fn rejects_castling_rights_on_same_side() {
+        assert_eq!(custom_error("4k3/8/8/8/8/8/8/RR2K3 w AB - 0 1"), Some("Your FEN has two castling rights on the same side of the king"));
+    }
+
+    #[test]
+    fn accepts_castling_rights_besides_corners() {
+        assert_eq!(custom_error("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1"), None);
+        // Rooks besides the corners are castled like in Chess960
+        assert_eq!(custom_error("4k3/8/8/8/8/8/8/1R2K1R1 w KQ - 0 1"), None);
+    }
+
+    #[test]
+    fn rejects_chess960_index_out_of_range() {
+        assert!(chess960_position(CHESS960_POSITIONS).is_none());
+        assert!(StartPosition::chess960(Some(CHESS960_POSITIONS)).is_none());
+        assert!(StartPosition::chess960(Some(u16::MAX)).is_none());
+        assert!(StartPosition::chess960(Some(CHESS960_POSITIONS - 1)).is_some());
+    }
+}
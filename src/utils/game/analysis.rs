@@ -0,0 +1,84 @@
+// Importing necessary modules and structures from the `serde` and `shakmaty` crates.
+use serde::Serialize;
+use shakmaty::{EnPassantMode, Position};
+use shakmaty::variant::VariantPosition;
+use shakmaty::fen::Fen;
+use shakmaty::san::SanPlus;
+
+// Importing the search result types.
+use crate::utils::game::generator::{PvLine, Score, SearchResult, Wdl};
+
+// Structure representing the analysis of a position, which is returned as JSON.
+#[derive(Serialize)]
+pub struct Analysis {
+    pub fen: String,
+    // Score of the best line from the view of the side to move, `None` if the game is already over.
+    pub score: Option<Score>,
+    pub depth: u32,
+    pub wdl: Option<WdlProbabilities>,
+    // The best lines of the engine, ordered by their rank.
+    pub lines: Vec<AnalysisLine>,
+}
+
+// Structure representing one line of an analysis in SAN and UCI notation.
+#[derive(Serialize)]
+pub struct AnalysisLine {
+    pub multipv: u32,
+    pub depth: u32,
+    pub score: Score,
+    // The wdl of the engine, which is estimated out of the score if the engine does not report one.
+    pub wdl: WdlProbabilities,
+    pub san: Vec<String>,
+    pub uci: Vec<String>,
+}
+
+// Structure representing the win, draw and loss probabilities from the view of the side to move.
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct WdlProbabilities {
+    pub win: f64,
+    pub draw: f64,
+    pub loss: f64,
+}
+
+impl Analysis {
+    // Method to create a new `Analysis` out of the search result of the given board.
+    pub fn new(board: &VariantPosition, result: SearchResult) -> Self {
+        let lines: Vec<AnalysisLine> = result.lines.into_iter().map(|line| AnalysisLine::new(board, line)).collect();
+        let best = lines.first();
+        Analysis {
+            fen: Fen::from_position(board.clone(), EnPassantMode::Legal).to_string(),
+            score: best.map(|line| line.score),
+            depth: result.depth,
+            wdl: best.map(|line| line.wdl),
+            lines,
+        }
+    }
+}
+
+impl AnalysisLine {
+    // Method to create a new `AnalysisLine`, the moves are converted into notations on the way through the line.
+    fn new(board: &VariantPosition, line: PvLine) -> Self {
+        let mut position = board.clone();
+        let uci = line.moves.iter().map(|mov| mov.to_uci(board.castles().mode()).to_string()).collect();
+        let san = line.moves.iter().map(|mov| SanPlus::from_move_and_play_unchecked(&mut position, mov).to_string()).collect();
+        AnalysisLine {
+            multipv: line.multipv,
+            depth: line.depth,
+            score: line.score,
+            wdl: WdlProbabilities::from(line.estimated_wdl()),
+            san,
+            uci,
+        }
+    }
+}
+
+impl From<Wdl> for WdlProbabilities {
+    // The engine reports permill, which are converted into probabilities.
+    fn from(wdl: Wdl) -> Self {
+        WdlProbabilities {
+            win: wdl.win as f64 / 1000.,
+            draw: wdl.draw as f64 / 1000.,
+            loss: wdl.loss as f64 / 1000.,
+        }
+    }
+}
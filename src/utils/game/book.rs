@@ -0,0 +1,193 @@
+// Importing necessary modules and structures from the `std`, `rand`, `rocket`, `serde` and `shakmaty` crates.
+use std::path::PathBuf;
+use std::sync::Arc;
+use rand::distributions::WeightedIndex;
+use rand::prelude::Distribution;
+use rocket::async_trait;
+use serde::Deserialize;
+use shakmaty::{Chess, EnPassantMode, Move, Position, Role, Square};
+use shakmaty::variant::VariantPosition;
+use shakmaty::uci::Uci;
+use shakmaty::zobrist::{Zobrist64, ZobristHash};
+
+// Importing the internal result type, the difficulty and the `MoveGenerator` trait.
+use crate::utils::errors::internal::InternalResult;
+use crate::utils::game::DIFFICULTY;
+use crate::utils::game::generator::{MoveGenerator, SearchLimits, SearchResult};
+
+// Size of a single entry of a Polyglot book in bytes.
+const ENTRY_SIZE: usize = 16;
+
+// Structure representing the book of a single difficulty in the `books` config section.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BookSettings {
+    // Path of the Polyglot `.bin` file.
+    pub path: PathBuf,
+    // Amount of plies after which the book is not used anymore.
+    #[serde(default = "default_max_depth")]
+    pub max_depth: u32,
+}
+
+// Function to get the max book depth, if it is not configured.
+fn default_max_depth() -> u32 {
+    16
+}
+
+// Structure representing the `books` config section, difficulties without a book always search.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BookConfig {
+    pub easy: Option<BookSettings>,
+    pub medium: Option<BookSettings>,
+    pub hard: Option<BookSettings>,
+    pub adaptive: Option<BookSettings>,
+}
+
+// Structure representing a single entry of a Polyglot book.
+struct BookEntry {
+    key: u64,
+    mov: u16,
+    weight: u16,
+}
+
+// The `OpeningBook` structure holds the entries of a Polyglot book, sorted by their key like in the file.
+pub struct OpeningBook {
+    entries: Vec<BookEntry>,
+    max_depth: u32,
+}
+
+impl OpeningBook {
+    // Method to read the Polyglot book of the given settings.
+    pub fn open(settings: &BookSettings) -> InternalResult<Self> {
+        let bytes = std::fs::read(&settings.path).map_err(|_| "BOOK: Could not read the book file")?;
+        if bytes.len() % ENTRY_SIZE != 0 {
+            return Err("BOOK: The book file is no Polyglot book");
+        }
+        let mut entries: Vec<BookEntry> = bytes.chunks_exact(ENTRY_SIZE).map(|chunk| BookEntry {
+            key: u64::from_be_bytes(chunk[0..8].try_into().unwrap_or_default()),
+            mov: u16::from_be_bytes([chunk[8], chunk[9]]),
+            weight: u16::from_be_bytes([chunk[10], chunk[11]]),
+        }).collect();
+        // Polyglot books are sorted already, but a binary search must not depend on it
+        entries.sort_by_key(|entry| entry.key);
+        Ok(OpeningBook {
+            entries,
+            max_depth: settings.max_depth,
+        })
+    }
+
+    // Method to get every legal book move of the position together with its weight.
+    pub fn moves(&self, board: &Chess) -> Vec<(Move, u16)> {
+        let key = board.zobrist_hash::<Zobrist64>(EnPassantMode::Legal).0;
+        let start = self.entries.partition_point(|entry| entry.key < key);
+        self.entries[start..].iter()
+            .take_while(|entry| entry.key == key)
+            .filter_map(|entry| Some((decode_move(entry.mov, board)?, entry.weight)))
+            .collect()
+    }
+
+    // Method to pick a book move with a probability proportional to its weight, `None` if the position is out of book.
+    pub fn pick(&self, board: &Chess) -> Option<Move> {
+        if ply(board) >= self.max_depth {
+            return None;
+        }
+        let moves = self.moves(board);
+        let distribution = WeightedIndex::new(moves.iter().map(|(_, weight)| *weight)).ok()?;
+        let index = distribution.sample(&mut rand::thread_rng());
+        Some(moves[index].0.clone())
+    }
+}
+
+// Function to decode a Polyglot move, castling is encoded as the king capturing its own rook.
+fn decode_move(mov: u16, board: &Chess) -> Option<Move> {
+    let to = Square::new(u32::from(mov & 0x3f));
+    let from = Square::new(u32::from((mov >> 6) & 0x3f));
+    let promotion = match (mov >> 12) & 0x7 {
+        0 => None,
+        1 => Some(Role::Knight),
+        2 => Some(Role::Bishop),
+        3 => Some(Role::Rook),
+        4 => Some(Role::Queen),
+        _ => return None,
+    };
+    Uci::Normal { from, to, promotion }.to_move(board).ok()
+}
+
+// Function to get the amount of plies played since the start position.
+fn ply(board: &Chess) -> u32 {
+    (board.fullmoves().get() - 1) * 2 + board.turn().fold_wb(0, 1)
+}
+
+// Structure representing the books of every difficulty.
+#[derive(Clone, Default)]
+pub struct OpeningBooks {
+    easy: Option<Arc<OpeningBook>>,
+    medium: Option<Arc<OpeningBook>>,
+    hard: Option<Arc<OpeningBook>>,
+    adaptive: Option<Arc<OpeningBook>>,
+}
+
+impl OpeningBooks {
+    // Method to read every configured book, fails if one of them can not be read.
+    pub fn load(config: &BookConfig) -> InternalResult<Self> {
+        let open = |settings: &Option<BookSettings>| -> InternalResult<Option<Arc<OpeningBook>>> {
+            let Some(settings) = settings else {
+                return Ok(None);
+            };
+            match OpeningBook::open(settings) {
+                Ok(book) => {
+                    info!("BOOK: Loaded {} entries of {}", book.entries.len(), settings.path.display());
+                    Ok(Some(Arc::new(book)))
+                }
+                Err(err) => {
+                    error!("BOOK: Could not load {}: {}", settings.path.display(), err);
+                    Err(err)
+                }
+            }
+        };
+        Ok(OpeningBooks {
+            easy: open(&config.easy)?,
+            medium: open(&config.medium)?,
+            hard: open(&config.hard)?,
+            adaptive: open(&config.adaptive)?,
+        })
+    }
+
+    // Method to get the book of a difficulty.
+    pub fn get(&self, difficulty: &DIFFICULTY) -> Option<Arc<OpeningBook>> {
+        match difficulty {
+            DIFFICULTY::EASY => self.easy.clone(),
+            DIFFICULTY::MEDIUM => self.medium.clone(),
+            DIFFICULTY::HARD => self.hard.clone(),
+            DIFFICULTY::ADAPTIVE => self.adaptive.clone(),
+        }
+    }
+}
+
+// The `BookMover` structure plays book moves and asks the wrapped move generator once the position is out of book.
+pub struct BookMover {
+    book: Arc<OpeningBook>,
+    inner: Box<dyn MoveGenerator>,
+}
+
+impl BookMover {
+    // Method to create a new `BookMover` around the given move generator.
+    pub fn new(book: Arc<OpeningBook>, inner: Box<dyn MoveGenerator>) -> Self {
+        BookMover { book, inner }
+    }
+}
+
+#[async_trait]
+impl MoveGenerator for BookMover {
+    async fn search(&mut self, board: &VariantPosition, limits: &SearchLimits) -> InternalResult<SearchResult> {
+        // The books only contain positions of standard chess
+        let VariantPosition::Chess(chess) = board else {
+            return self.inner.search(board, limits).await;
+        };
+        match self.book.pick(chess) {
+            Some(mov) => Ok(SearchResult::from_move(mov)),
+            None => self.inner.search(board, limits).await,
+        }
+    }
+}
@@ -1,13 +1,56 @@
-use std::io::{BufRead, BufReader, Write};
-use std::process::{Child, ChildStdin, Command, Stdio};
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+use std::process::Stdio;
+use std::sync::Arc;
 use std::time::Duration;
-use shakmaty::{Chess, EnPassantMode, Move};
+use rocket::async_trait;
+use shakmaty::{CastlingMode, EnPassantMode, Move, Position};
+use shakmaty::variant::{Variant, VariantPosition};
 use shakmaty::fen::Fen;
 use shakmaty::uci::Uci;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, Command};
 use tokio::sync::mpsc::{channel, Receiver};
 use tokio::task::JoinHandle;
+use tokio::time::{timeout_at, Instant};
 use crate::utils::errors::internal::InternalResult;
 use crate::utils::game::find_with_auto_promotion;
+use crate::utils::game::generator::{MoveGenerator, PvLine, SearchLimits, SearchResult};
+use crate::utils::game::thinking::ThinkingEvent;
+
+// The `config` module contains the `engine` config section.
+mod config;
+// The `uci` module contains the parsers for the output of the engine.
+mod uci;
+
+pub use config::EngineConfig;
+use config::EngineProtocol;
+use uci::{UciInfo, UciMessage, UciOption, UciOptionKind};
+
+// Time the engine may take to answer a command outside of a search, e.g. `uci` or `isready`.
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(10);
+// Time the engine may take to send its best move after it has been told to `stop`.
+const STOP_TIMEOUT: Duration = Duration::from_secs(1);
+
+// Enum representing the state of the UCI session with the engine.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UciState {
+    // The process is running, but the handshake has not been completed yet.
+    Started,
+    // The engine completed the handshake and is waiting for commands.
+    Ready,
+    // The engine is searching and has not sent its `bestmove` yet.
+    Searching,
+}
+
+// Structure representing the options of the next searches, which are applied by `configure` and again after a restart.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SessionOptions {
+    // The engine searches at full strength if no elo is set.
+    pub elo: Option<u16>,
+    // The amount of principal variations, the value of the engine config is used if it is not set.
+    pub multipv: Option<u32>,
+}
 
 pub struct Engine {
     // Dead code needs to be allowed here, because the child guard is needed to ensure subprocess kill after drop
@@ -16,12 +59,25 @@ pub struct Engine {
     child_guard: ChildGuard,
     sender: ChildStdin,
     receiver: Receiver<String>,
-    depth: u32,
+    state: UciState,
+    name: Option<String>,
+    options: Vec<UciOption>,
+    config: Arc<EngineConfig>,
+    // The options of the last `configure`, which have to be applied again after a restart.
+    session: Option<SessionOptions>,
+    // The expected position the engine is pondering on, the engine is searching until `ponderhit` or `stop`.
+    pondering: Option<VariantPosition>,
+    // If `UCI_Chess960` is enabled, which follows the castling mode of the searched positions.
+    chess960: bool,
+    // The value of `UCI_Variant`, which follows the variant of the searched positions.
+    variant: Variant,
 }
 
 impl Engine {
-    pub fn new(depth: u32, elo: u16) -> Option<Self> {
-        let child = Command::new("stockfish")
+    // Spawns the configured engine binary searching at full strength and completes the UCI handshake
+    pub async fn new(config: Arc<EngineConfig>) -> Option<Self> {
+        let child = Command::new(&config.binary)
+            .args(&config.args)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .spawn().ok()?;
@@ -30,14 +86,13 @@ impl Engine {
         let _stdout = child_guard.child.stdout.take()?;
 
         let (tx, rx) = channel(1024);
-        let reader = BufReader::new(_stdout);
+        let mut reader = BufReader::new(_stdout).lines();
 
+        // Every line of the engine is forwarded, the channel is closed once the process exits
         let handle = tokio::spawn(async move {
-            for line in reader.lines() {
-                let line = line.unwrap_or("".to_string());
-                if line.contains("bestmove") {
-                    let mv = &line[9..13];
-                    let _ = tx.send(String::from(mv)).await;
+            while let Ok(Some(line)) = reader.next_line().await {
+                if tx.send(line).await.is_err() {
+                    break;
                 }
             }
         });
@@ -46,41 +101,403 @@ impl Engine {
             child_guard,
             sender: _stdin,
             receiver: rx,
-            depth,
+            state: UciState::Started,
+            name: None,
+            options: Vec::new(),
+            config,
+            session: None,
+            pondering: None,
+            chess960: false,
+            variant: Variant::Chess,
         };
-        engine.set_elo(elo).ok()?;
+        engine.handshake().await.ok()?;
         Some(engine)
     }
-    fn set_elo(&mut self, elo: u16) -> InternalResult<()> {
-        let uci_limit_cmd = "setoption name UCI_LimitStrength value true".to_string();
-        let uci_elo = format!("setoption name UCI_Elo value {}", elo);
-        let _ = self.send(uci_limit_cmd).map_err(|_| "ENGINE: Could not send message to subprocess")?;
-        let _ = self.send(uci_elo).map_err(|_| "ENGINE: Could not send message to subprocess")?;
+
+    // Sends `uci`, collects the id and the options of the engine until `uciok` and waits until the engine is ready
+    async fn handshake(&mut self) -> InternalResult<()> {
+        match self.config.protocol {
+            EngineProtocol::Uci => self.send("uci".to_string()).await?,
+        }
+        loop {
+            match UciMessage::parse(&self.receive().await?) {
+                UciMessage::UciOk => break,
+                UciMessage::IdName(name) => self.name = Some(name),
+                UciMessage::Option(option) => self.options.push(option),
+                _ => {}
+            }
+        }
+        self.sync().await?;
+        self.state = UciState::Ready;
+        Ok(())
+    }
+
+    // Sends `isready` and waits for `readyok`, so that all previous commands are processed
+    async fn sync(&mut self) -> InternalResult<()> {
+        self.send("isready".to_string()).await?;
+        while UciMessage::parse(&self.receive().await?) != UciMessage::ReadyOk {}
+        Ok(())
+    }
+
+    // Returns the name the engine reported during the handshake
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    // Returns an option the engine reported during the handshake
+    pub fn option(&self, name: &str) -> Option<&UciOption> {
+        self.options.iter().find(|option| option.name.eq_ignore_ascii_case(name))
+    }
+
+    // Returns the elo range the strength of the engine can be limited to, `None` if the engine can not limit its strength
+    pub fn elo_range(&self) -> Option<RangeInclusive<u16>> {
+        self.option("UCI_LimitStrength")?;
+        match self.option("UCI_Elo")?.kind {
+            UciOptionKind::Spin { min, max, .. } => Some(u16::try_from(min).ok()?..=u16::try_from(max).ok()?),
+            _ => None,
+        }
+    }
+
+    // Returns the variants the engine is able to play besides standard chess, which it reports as the values of `UCI_Variant`
+    pub fn variants(&self) -> Vec<Variant> {
+        match self.option("UCI_Variant").map(|option| &option.kind) {
+            Some(UciOptionKind::Combo { vars, .. }) => vars.iter()
+                .filter_map(|var| Variant::from_uci(var).ok())
+                .filter(|variant| *variant != Variant::Chess)
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    // Sets an option after checking its value against the type and the limits the engine reported
+    pub async fn set_option(&mut self, name: &str, value: &str) -> InternalResult<()> {
+        let option = self.option(name).ok_or("ENGINE: The engine does not support this option")?;
+        option.validate(value)?;
+        self.send_option(name, value).await
+    }
+
+    // Applies every option of the engine config, fails if the engine rejects one of them
+    pub async fn apply_config(&mut self) -> InternalResult<()> {
+        for (name, value) in self.config.uci_options() {
+            if let Err(err) = self.set_option(&name, &value).await {
+                error!("ENGINE: Invalid option {} = {}: {}", name, value, err);
+                return Err(err);
+            }
+        }
+        // Win, draw and loss probabilities are part of every analysis, if the engine is able to report them
+        if self.option("UCI_ShowWDL").is_some() {
+            self.send_option("UCI_ShowWDL", "true").await?;
+        }
+        // Engines plan their time differently if they are allowed to ponder
+        if self.config.ponder && self.option("Ponder").is_some() {
+            self.send_option("Ponder", "true").await?;
+        }
+        self.sync().await
+    }
+
+    async fn send_option(&mut self, name: &str, value: &str) -> InternalResult<()> {
+        self.ensure_ready()?;
+        self.option(name).ok_or("ENGINE: The engine does not support this option")?;
+        self.send(format!("setoption name {} value {}", name, value)).await
+    }
+
+    // Resets the engine for a new game and applies the options of the next searches, a crashed engine is restarted
+    pub async fn configure(&mut self, session: SessionOptions) -> InternalResult<()> {
+        self.session = Some(session);
+        if let Err(err) = self.apply_session().await {
+            warn!("ENGINE: Could not configure the engine ({}), restarting it", err);
+            self.restart().await?;
+        }
         Ok(())
     }
 
-    fn send(&mut self, message: String) -> InternalResult<()> {
-        self.sender.write_all(format!("{}\n", message).as_bytes()).map_err(|_| "ENGINE: Could not write to stdout")
+    async fn apply_session(&mut self) -> InternalResult<()> {
+        let session = self.session.ok_or("ENGINE: The engine has not been configured")?;
+        self.stop_pondering().await?;
+        self.ensure_ready()?;
+        self.send("ucinewgame".to_string()).await?;
+        match session.elo {
+            Some(elo) => {
+                self.send_option("UCI_LimitStrength", "true").await?;
+                self.send_option("UCI_Elo", &elo.to_string()).await?;
+            }
+            // Engines that can not limit their strength always search at full strength
+            None if self.option("UCI_LimitStrength").is_some() => self.send_option("UCI_LimitStrength", "false").await?,
+            None => {}
+        }
+        if self.option("MultiPV").is_some() {
+            let multipv = session.multipv.or(self.config.multipv).unwrap_or(1);
+            self.send_option("MultiPV", &multipv.to_string()).await?;
+        }
+        self.sync().await
+    }
+
+    // Replaces the process with a new one and applies the config and the elo again
+    async fn restart(&mut self) -> InternalResult<()> {
+        let mut engine = Engine::new(self.config.clone()).await.ok_or("ENGINE: Could not restart the engine")?;
+        engine.apply_config().await?;
+        if self.session.is_some() {
+            engine.session = self.session;
+            engine.apply_session().await?;
+        }
+        // The old process is killed once it is dropped
+        *self = engine;
+        info!("ENGINE: Restarted {}", self.name().unwrap_or("unknown engine"));
+        Ok(())
     }
+
+    fn ensure_ready(&self) -> InternalResult<()> {
+        match self.state {
+            UciState::Ready => Ok(()),
+            UciState::Started => Err("ENGINE: The handshake has not been completed"),
+            UciState::Searching => Err("ENGINE: The engine is still searching"),
+        }
+    }
+
+    async fn send(&mut self, message: String) -> InternalResult<()> {
+        self.sender.write_all(format!("{}\n", message).as_bytes()).await.map_err(|_| "ENGINE: Could not write to stdin")?;
+        self.sender.flush().await.map_err(|_| "ENGINE: Could not write to stdin")
+    }
+
     async fn receive(&mut self) -> InternalResult<String> {
-        self.receiver.recv().await.ok_or("ENGINE: Could not receive Engine stdout")
+        self.receive_until(Instant::now() + RESPONSE_TIMEOUT).await
+    }
+
+    // Receives the next line, fails if the process exited or did not answer until the deadline
+    async fn receive_until(&mut self, deadline: Instant) -> InternalResult<String> {
+        match timeout_at(deadline, self.receiver.recv()).await {
+            Ok(Some(line)) => Ok(line),
+            Ok(None) => Err("ENGINE: Could not receive Engine stdout"),
+            Err(_) => Err("ENGINE: The engine did not respond in time"),
+        }
+    }
+
+    // Returns if the engine is pondering on an expected position
+    pub fn is_pondering(&self) -> bool {
+        self.pondering.is_some()
+    }
+
+    // Starts to search the expected position after the reply of the user, until the user actually moves
+    pub async fn ponder(&mut self, board: &VariantPosition, limits: &SearchLimits) -> InternalResult<()> {
+        self.start_search(board, &go_command(limits).replacen("go", "go ponder", 1)).await?;
+        self.pondering = Some(board.clone());
+        Ok(())
+    }
+
+    // Stops pondering and throws the result away, the engine is ready afterwards
+    pub async fn stop_pondering(&mut self) -> InternalResult<()> {
+        if self.pondering.take().is_none() {
+            return Ok(());
+        }
+        self.send("stop".to_string()).await?;
+        let deadline = Instant::now() + STOP_TIMEOUT;
+        while !matches!(UciMessage::parse(&self.receive_until(deadline).await?), UciMessage::BestMove { .. }) {}
+        self.state = UciState::Ready;
+        Ok(())
+    }
+
+    // Searches the position once, the search is stopped if it takes longer than the deadline
+    async fn run_search(&mut self, board: &VariantPosition, limits: &SearchLimits, deadline: Instant) -> InternalResult<SearchResult> {
+        self.start_search(board, &go_command(limits)).await?;
+        self.finish_search(board, limits, deadline).await
     }
-    pub async fn gen_next_move(&mut self, board: &Chess) -> InternalResult<Move> {
+
+    async fn start_search(&mut self, board: &VariantPosition, go_cmd: &str) -> InternalResult<()> {
+        self.ensure_ready()?;
+        // The castling rights of Chess960 positions are sent as the files of their rooks, which the engine only understands in Chess960 mode
+        let chess960 = board.castles().mode() == CastlingMode::Chess960;
+        if chess960 != self.chess960 {
+            self.send_option("UCI_Chess960", &chess960.to_string()).await.map_err(|_| "ENGINE: The engine does not support Chess960")?;
+            self.chess960 = chess960;
+        }
+        let variant = board.variant();
+        if variant != self.variant {
+            self.set_option("UCI_Variant", variant.uci()).await.map_err(|_| "ENGINE: The engine does not support this variant")?;
+            self.variant = variant;
+        }
         let fen = Fen::from_position(board.clone(), EnPassantMode::Legal);
-        let fen_cmd = format!("position fen {}", fen.to_string());
-        let depth_cmd = format!("go depth {}", self.depth);
+        let fen_cmd = format!("position fen {}", fen);
+
+        self.send(fen_cmd).await.map_err(|_| "ENGINE: Could not send fen command")?;
+        self.send(go_cmd.to_string()).await.map_err(|_| "ENGINE: Could not send go command")?;
+        self.state = UciState::Searching;
+        Ok(())
+    }
+
+    // Collects the output of a running search, the search is stopped if it takes longer than the deadline
+    // Every new best line is sent to the thinking stream of the limits, if there is one
+    async fn finish_search(&mut self, board: &VariantPosition, limits: &SearchLimits, deadline: Instant) -> InternalResult<SearchResult> {
+        // The search is only complete once the engine announces its best move
+        let mut collector = InfoCollector::default();
+        let mut deadline = deadline;
+        let mut stopped = false;
+        let (best, ponder) = loop {
+            let line = match self.receive_until(deadline).await {
+                Ok(line) => line,
+                // An engine that exceeded its time gets one chance to answer `stop` before it is considered hung
+                Err(_) if !stopped && !self.receiver.is_closed() => {
+                    warn!("ENGINE: The search exceeded its deadline, stopping it");
+                    self.send("stop".to_string()).await?;
+                    deadline = Instant::now() + STOP_TIMEOUT;
+                    stopped = true;
+                    continue;
+                }
+                Err(err) => return Err(err),
+            };
+            match UciMessage::parse(&line) {
+                UciMessage::Info(info) => {
+                    let best_line = info.multipv.unwrap_or(1) == 1 && info.score.is_some() && !info.pv.is_empty();
+                    collector.add(info);
+                    if let (true, Some(thinking)) = (best_line, &limits.thinking) {
+                        if let Some(line) = collector.line(board, 1) {
+                            thinking.send(ThinkingEvent::info(board, &line, collector.nodes, collector.nps));
+                        }
+                    }
+                }
+                UciMessage::BestMove { best, ponder } => break (best, ponder),
+                _ => {}
+            }
+        };
+        self.state = UciState::Ready;
+        collector.into_result(board, best, ponder)
+    }
+
+    // Calculates the latest point in time the best move of a search has to be received
+    fn search_deadline(&self, board: &VariantPosition, limits: &SearchLimits) -> Instant {
+        let budget = limits.time_budget(board.turn()).unwrap_or_default();
+        Instant::now() + budget + Duration::from_millis(self.config.search_timeout)
+    }
+}
+
+#[async_trait]
+impl MoveGenerator for Engine {
+    // A crashed or hung engine is restarted and the position is searched again, so the game can continue
+    // If the user played the expected move, the engine keeps the search it pondered, otherwise the position is searched from scratch
+    async fn search(&mut self, board: &VariantPosition, limits: &SearchLimits) -> InternalResult<SearchResult> {
+        let deadline = self.search_deadline(board, limits);
+        let expected = self.pondering.as_ref().map(|expected| Fen::from_position(expected.clone(), EnPassantMode::Legal));
+        let result = match expected {
+            Some(expected) if expected == Fen::from_position(board.clone(), EnPassantMode::Legal) => {
+                debug!("ENGINE: Ponderhit");
+                self.pondering = None;
+                match self.send("ponderhit".to_string()).await {
+                    Ok(_) => self.finish_search(board, limits, deadline).await,
+                    Err(err) => Err(err),
+                }
+            }
+            _ => match self.stop_pondering().await {
+                Ok(_) => self.run_search(board, limits, deadline).await,
+                Err(err) => Err(err),
+            },
+        };
+        match result {
+            Ok(result) => Ok(result),
+            Err(err) => {
+                warn!("ENGINE: The search failed ({}), restarting the engine", err);
+                self.restart().await?;
+                let deadline = self.search_deadline(board, limits);
+                self.run_search(board, limits, deadline).await
+            }
+        }
+    }
+}
+
+// Function to build the `go` command out of the search limits
+fn go_command(limits: &SearchLimits) -> String {
+    let mut command = String::from("go");
+    if let Some(depth) = limits.depth {
+        command.push_str(&format!(" depth {}", depth));
+    }
+    if let Some(movetime) = limits.movetime {
+        command.push_str(&format!(" movetime {}", movetime));
+    }
+    if let Some(nodes) = limits.nodes {
+        command.push_str(&format!(" nodes {}", nodes));
+    }
+    if let Some(clock) = limits.clock {
+        command.push_str(&format!(" wtime {} btime {} winc {} binc {}", clock.white_ms, clock.black_ms, clock.white_inc_ms, clock.black_inc_ms));
+    }
+    // A search without any limit would never send its best move
+    if command == "go" {
+        command.push_str(" depth 1");
+    }
+    command
+}
 
-        let _ = self.send(fen_cmd).map_err(|_| "ENGINE: Could not send fen command")?;
-        let _ = self.send(depth_cmd).map_err(|_| "ENGINE: Could not send depth command")?;
-        tokio::time::sleep(Duration::from_millis(250)).await;
-        let mv = self.receive().await.map_err(|_| "ENGINE: Could not receive generated move")?;
+// The `InfoCollector` structure gathers the `info` lines of a search.
+#[derive(Default)]
+struct InfoCollector {
+    // The latest line with a score and a principal variation of every multipv rank.
+    lines: BTreeMap<u32, UciInfo>,
+    depth: u32,
+    seldepth: u32,
+    nodes: u64,
+    nps: u64,
+}
 
-        let uci: Uci = mv.parse().map_err(|_| "ENGINE: Generated move is no valid UCI")?;
-        let mov = find_with_auto_promotion(&uci, &board).ok_or("ENGINE: Generated move is not valid")?;
-        Ok(mov)
+impl InfoCollector {
+    fn add(&mut self, info: UciInfo) {
+        self.depth = info.depth.unwrap_or(self.depth);
+        self.seldepth = info.seldepth.unwrap_or(self.seldepth);
+        self.nodes = info.nodes.unwrap_or(self.nodes);
+        self.nps = info.nps.unwrap_or(self.nps);
+        if info.score.is_some() && !info.pv.is_empty() {
+            self.lines.insert(info.multipv.unwrap_or(1), info);
+        }
+    }
+
+    // Converts the latest line of a multipv rank into a `PvLine`, the moves are validated against the searched board
+    fn line(&self, board: &VariantPosition, multipv: u32) -> Option<PvLine> {
+        let info = self.lines.get(&multipv)?;
+        Some(PvLine {
+            multipv,
+            depth: info.depth.unwrap_or(self.depth),
+            score: info.score?,
+            wdl: info.wdl,
+            moves: uci_to_moves(board, &info.pv),
+        })
+    }
+
+    // Converts the collected output into a `SearchResult`, the moves are validated against the searched board
+    fn into_result(self, board: &VariantPosition, best: Option<Uci>, ponder: Option<Uci>) -> InternalResult<SearchResult> {
+        let best_move = match best {
+            Some(uci) => Some(find_with_auto_promotion(&uci, board).ok_or("ENGINE: Generated move is not valid")?),
+            None => None,
+        };
+        let ponder = best_move.as_ref().zip(ponder).and_then(|(best_move, ponder)| {
+            let mut after = board.clone();
+            after.play_unchecked(best_move);
+            ponder.to_move(&after).ok()
+        });
+        let lines = self.lines.keys().filter_map(|multipv| self.line(board, *multipv)).collect();
+        Ok(SearchResult {
+            best_move,
+            ponder,
+            lines,
+            depth: self.depth,
+            seldepth: self.seldepth,
+            nodes: self.nodes,
+            nps: self.nps,
+        })
     }
 }
 
+// Function to convert a principal variation into moves, the variation is cut off at the first illegal move
+fn uci_to_moves(board: &VariantPosition, pv: &[Uci]) -> Vec<Move> {
+    let mut position = board.clone();
+    let mut moves = Vec::with_capacity(pv.len());
+    for uci in pv {
+        match uci.to_move(&position) {
+            Ok(mov) => {
+                position.play_unchecked(&mov);
+                moves.push(mov);
+            }
+            Err(_) => break,
+        }
+    }
+    moves
+}
+
 impl Drop for Engine{
     fn drop(&mut self) {
         self.handle.abort();
@@ -93,6 +510,6 @@ struct ChildGuard {
 
 impl Drop for ChildGuard {
     fn drop(&mut self) {
-        let _ = self.child.kill();
+        let _ = self.child.start_kill();
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,141 @@
+// Importing necessary modules and structures from the `std` and `serde` crates.
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+use serde::Deserialize;
+
+// Importing the internal result type.
+use crate::utils::errors::internal::InternalResult;
+
+// Options which are set for every search out of the difficulty, so they must not be configured.
+const RESERVED_OPTIONS: [&str; 2] = ["UCI_LimitStrength", "UCI_Elo"];
+
+// Enum representing the protocol the engine is talked to with, only UCI engines are supported.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum EngineProtocol {
+    #[default]
+    Uci,
+}
+
+// Structure representing the `engine` config section and the entries of the engine registry, every missing key keeps the default of the engine.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EngineConfig {
+    // Path or name of the engine binary, names are looked up in the `PATH`.
+    pub binary: PathBuf,
+    // Command line arguments passed to the engine binary.
+    pub args: Vec<String>,
+    pub protocol: EngineProtocol,
+    // Amount of processes of the engine, the `engine` section defaults to `engine_pool_size` and registered engines to one process.
+    pub pool_size: Option<usize>,
+    pub threads: Option<u32>,
+    // Size of the hash table in MB.
+    pub hash: Option<u32>,
+    pub multipv: Option<u32>,
+    // NNUE network file, which is passed to the engine as `EvalFile`.
+    pub eval_file: Option<PathBuf>,
+    // Any other option of the engine, which is sent as `setoption name <key> value <value>`.
+    pub options: BTreeMap<String, OptionValue>,
+    // Time in milliseconds a search may take beyond its time budget, before the engine is considered hung and restarted.
+    pub search_timeout: u64,
+    // Whether the engine thinks on the expected reply while the user is to move.
+    pub ponder: bool,
+}
+
+// Enum representing the value of a configured option, so options can be written as native TOML values.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum OptionValue {
+    Bool(bool),
+    Int(i64),
+    Text(String),
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        EngineConfig {
+            binary: PathBuf::from("stockfish"),
+            args: Vec::new(),
+            protocol: EngineProtocol::Uci,
+            pool_size: None,
+            threads: None,
+            hash: None,
+            multipv: None,
+            eval_file: None,
+            options: BTreeMap::new(),
+            search_timeout: 30_000,
+            ponder: true,
+        }
+    }
+}
+
+impl EngineConfig {
+    // Method to check the configuration before any engine is started, the options themselves are checked against the engine after the handshake.
+    pub fn validate(&self) -> InternalResult<()> {
+        if self.binary.as_os_str().is_empty() {
+            return Err("ENGINE-CONFIG: The engine binary must not be empty");
+        }
+        if self.pool_size == Some(0) {
+            return Err("ENGINE-CONFIG: pool_size must be at least 1");
+        }
+        if self.threads == Some(0) {
+            return Err("ENGINE-CONFIG: threads must be at least 1");
+        }
+        if self.hash == Some(0) {
+            return Err("ENGINE-CONFIG: hash must be at least 1 MB");
+        }
+        if self.multipv == Some(0) {
+            return Err("ENGINE-CONFIG: multipv must be at least 1");
+        }
+        if self.search_timeout == 0 {
+            return Err("ENGINE-CONFIG: search_timeout must be at least 1 ms");
+        }
+        if self.eval_file.as_ref().is_some_and(|file| !file.is_file()) {
+            return Err("ENGINE-CONFIG: The eval_file does not exist");
+        }
+        for name in self.options.keys() {
+            if name.trim().is_empty() {
+                return Err("ENGINE-CONFIG: Option names must not be empty");
+            }
+            if RESERVED_OPTIONS.iter().any(|reserved| reserved.eq_ignore_ascii_case(name)) {
+                return Err("ENGINE-CONFIG: UCI_LimitStrength and UCI_Elo are set by the difficulty and can not be configured");
+            }
+            if name.eq_ignore_ascii_case("Ponder") {
+                return Err("ENGINE-CONFIG: Ponder has to be set with the ponder key");
+            }
+            if self.dedicated_options().any(|(dedicated, _)| dedicated.eq_ignore_ascii_case(name)) {
+                return Err("ENGINE-CONFIG: Threads, Hash, MultiPV and EvalFile have to be set with their dedicated keys");
+            }
+        }
+        Ok(())
+    }
+
+    // Method to get every configured option as name and value, in the order they have to be sent to the engine.
+    pub fn uci_options(&self) -> Vec<(String, String)> {
+        self.dedicated_options()
+            .filter_map(|(name, value)| Some((name.to_string(), value?)))
+            .chain(self.options.iter().map(|(name, value)| (name.clone(), value.to_string())))
+            .collect()
+    }
+
+    // Method to get the options which have their own config key.
+    fn dedicated_options(&self) -> impl Iterator<Item = (&'static str, Option<String>)> {
+        [
+            ("Threads", self.threads.map(|threads| threads.to_string())),
+            ("Hash", self.hash.map(|hash| hash.to_string())),
+            ("MultiPV", self.multipv.map(|multipv| multipv.to_string())),
+            ("EvalFile", self.eval_file.as_ref().map(|file| file.display().to_string())),
+        ].into_iter()
+    }
+}
+
+impl fmt::Display for OptionValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OptionValue::Bool(value) => write!(f, "{}", value),
+            OptionValue::Int(value) => write!(f, "{}", value),
+            OptionValue::Text(value) => write!(f, "{}", value),
+        }
+    }
+}
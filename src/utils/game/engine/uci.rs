@@ -0,0 +1,269 @@
+// Importing necessary modules and structures from the `shakmaty` crate and the shared search result types.
+use shakmaty::uci::Uci;
+use crate::utils::errors::internal::InternalResult;
+use crate::utils::game::generator::{Score, Wdl};
+
+// Enum representing the type and the limits of an option, which the engine announced during the handshake.
+#[derive(Clone, Debug, PartialEq)]
+pub enum UciOptionKind {
+    Check { default: bool },
+    Spin { default: i64, min: i64, max: i64 },
+    Combo { default: String, vars: Vec<String> },
+    Button,
+    String { default: String },
+}
+
+// Structure representing an option out of an `option name ... type ...` line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UciOption {
+    pub name: String,
+    pub kind: UciOptionKind,
+}
+
+impl UciOption {
+    // Method to parse an `option` line, returns `None` if the line is no valid option.
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut tokens = line.split_whitespace();
+        if tokens.next()? != "option" || tokens.next()? != "name" {
+            return None;
+        }
+
+        // The name may contain spaces, so it reaches up to the `type` keyword
+        let tokens: Vec<&str> = tokens.collect();
+        let type_index = tokens.iter().position(|token| *token == "type")?;
+        let name = tokens[..type_index].join(" ");
+        let kind = *tokens.get(type_index + 1)?;
+
+        // Every remaining keyword is followed by a value, which may contain spaces as well
+        let mut default = None;
+        let mut min = None;
+        let mut max = None;
+        let mut vars = Vec::new();
+        let mut keyword: Option<&str> = None;
+        let mut value: Vec<&str> = Vec::new();
+        for token in tokens[type_index + 2..].iter().copied().chain(std::iter::once("")) {
+            if matches!(token, "default" | "min" | "max" | "var" | "") {
+                let joined = value.join(" ");
+                match keyword {
+                    Some("default") => default = Some(joined),
+                    Some("min") => min = Some(joined),
+                    Some("max") => max = Some(joined),
+                    Some("var") => vars.push(joined),
+                    _ => {}
+                }
+                keyword = Some(token);
+                value.clear();
+            } else {
+                value.push(token);
+            }
+        }
+
+        let kind = match kind {
+            "check" => UciOptionKind::Check { default: default? == "true" },
+            "spin" => UciOptionKind::Spin {
+                default: default?.parse().ok()?,
+                min: min?.parse().ok()?,
+                max: max?.parse().ok()?,
+            },
+            "combo" => UciOptionKind::Combo { default: default?, vars },
+            "button" => UciOptionKind::Button,
+            "string" => UciOptionKind::String { default: default.unwrap_or_default() },
+            _ => return None,
+        };
+        Some(UciOption { name, kind })
+    }
+
+    // Method to check a value against the type and the limits of the option.
+    pub fn validate(&self, value: &str) -> InternalResult<()> {
+        match &self.kind {
+            UciOptionKind::Check { .. } if value != "true" && value != "false" => Err("ENGINE: The value of a check option must be true or false"),
+            UciOptionKind::Spin { min, max, .. } => match value.parse::<i64>() {
+                Ok(value) if (*min..=*max).contains(&value) => Ok(()),
+                Ok(_) => Err("ENGINE: The value of a spin option is out of its bounds"),
+                Err(_) => Err("ENGINE: The value of a spin option must be an integer"),
+            },
+            UciOptionKind::Combo { vars, .. } if !vars.iter().any(|var| var.eq_ignore_ascii_case(value)) => Err("ENGINE: The value of a combo option is none of its variants"),
+            _ => Ok(()),
+        }
+    }
+}
+
+// Structure representing the values of an `info` line, every value the engine did not send is `None`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UciInfo {
+    pub depth: Option<u32>,
+    pub seldepth: Option<u32>,
+    pub multipv: Option<u32>,
+    pub score: Option<Score>,
+    pub wdl: Option<Wdl>,
+    pub nodes: Option<u64>,
+    pub nps: Option<u64>,
+    pub time: Option<u64>,
+    pub pv: Vec<Uci>,
+}
+
+// Enum representing a single line of engine output.
+#[derive(Clone, Debug, PartialEq)]
+pub enum UciMessage {
+    IdName(String),
+    IdAuthor(String),
+    UciOk,
+    ReadyOk,
+    Option(UciOption),
+    // `best` is `None` if the engine answered `bestmove (none)`, because there is no legal move.
+    BestMove { best: Option<Uci>, ponder: Option<Uci> },
+    Info(UciInfo),
+    Unknown(String),
+}
+
+impl UciMessage {
+    // Method to parse a line of engine output, lines that are not understood are returned as `Unknown`.
+    pub fn parse(line: &str) -> Self {
+        let line = line.trim();
+        let mut tokens = line.split_whitespace();
+        let message = match tokens.next() {
+            Some("uciok") => Some(UciMessage::UciOk),
+            Some("readyok") => Some(UciMessage::ReadyOk),
+            Some("id") => match tokens.next() {
+                Some("name") => Some(UciMessage::IdName(tokens.collect::<Vec<_>>().join(" "))),
+                Some("author") => Some(UciMessage::IdAuthor(tokens.collect::<Vec<_>>().join(" "))),
+                _ => None,
+            },
+            Some("option") => UciOption::parse(line).map(UciMessage::Option),
+            Some("bestmove") => Self::parse_bestmove(tokens),
+            Some("info") => Self::parse_info(tokens).map(UciMessage::Info),
+            _ => None,
+        };
+        message.unwrap_or_else(|| UciMessage::Unknown(line.to_string()))
+    }
+
+    fn parse_bestmove<'a>(mut tokens: impl Iterator<Item=&'a str>) -> Option<Self> {
+        let best = parse_move(tokens.next()?)?;
+        let ponder = match tokens.next() {
+            Some("ponder") => parse_move(tokens.next()?)?,
+            _ => None,
+        };
+        Some(UciMessage::BestMove { best, ponder })
+    }
+
+    fn parse_info<'a>(mut tokens: impl Iterator<Item=&'a str>) -> Option<UciInfo> {
+        let mut info = UciInfo::default();
+        // Every keyword consumes its own values, so a keyword without a value can not shift the following ones
+        while let Some(keyword) = tokens.next() {
+            match keyword {
+                "depth" => info.depth = Some(tokens.next()?.parse().ok()?),
+                "seldepth" => info.seldepth = Some(tokens.next()?.parse().ok()?),
+                "multipv" => info.multipv = Some(tokens.next()?.parse().ok()?),
+                "nodes" => info.nodes = Some(tokens.next()?.parse().ok()?),
+                "nps" => info.nps = Some(tokens.next()?.parse().ok()?),
+                "time" => info.time = Some(tokens.next()?.parse().ok()?),
+                "score" => {
+                    let kind = tokens.next()?;
+                    let amount = tokens.next()?.parse().ok()?;
+                    info.score = match kind {
+                        "cp" => Some(Score::Cp(amount)),
+                        "mate" => Some(Score::Mate(amount)),
+                        _ => return None,
+                    };
+                }
+                "wdl" => {
+                    let mut wdl = tokens.by_ref().take(3).map(|value| value.parse().ok());
+                    info.wdl = Some(Wdl { win: wdl.next()??, draw: wdl.next()??, loss: wdl.next()?? });
+                }
+                "pv" => {
+                    // The principal variation is always the last value of a line
+                    info.pv = tokens.map(|mov| mov.parse().ok()).collect::<Option<Vec<Uci>>>()?;
+                    break;
+                }
+                // The rest of a string is free text
+                "string" => break,
+                // Values of unrelated keywords like `currmove` are skipped with them, a bound of a score has no value at all
+                "currmove" | "currmovenumber" | "hashfull" | "tbhits" | "sbhits" | "cpuload" => {
+                    tokens.next();
+                }
+                _ => {}
+            }
+        }
+        Some(info)
+    }
+}
+
+// Function to parse the move of a `bestmove` line, `(none)` and the null move mean that there is no move.
+fn parse_move(token: &str) -> Option<Option<Uci>> {
+    match token {
+        "(none)" | "0000" => Some(None),
+        token => token.parse().ok().map(Some),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Function to parse an `info` line, which fails the test if it is not parsed as one.
+    fn info(line: &str) -> UciInfo {
+        match UciMessage::parse(line) {
+            UciMessage::Info(info) => info,
+            message => panic!("{line} was parsed as {message:?}"),
+        }
+    }
+
+    #[test]
+    fn parses_centipawn_score() {
+        let info = info("info depth 12 seldepth 18 score cp 35 nodes 12000 nps 600000 time 20");
+        assert_eq!(info.depth, Some(12));
+        assert_eq!(info.seldepth, Some(18));
+        assert_eq!(info.score, Some(Score::Cp(35)));
+        assert_eq!(info.nodes, Some(12000));
+        assert_eq!(info.nps, Some(600000));
+        assert_eq!(info.time, Some(20));
+    }
+
+    #[test]
+    fn parses_mate_score() {
+        assert_eq!(info("info depth 30 score mate -3 nodes 5").score, Some(Score::Mate(-3)));
+        assert_eq!(info("info depth 30 score mate -3 nodes 5").nodes, Some(5));
+    }
+
+    #[test]
+    fn skips_score_bounds() {
+        let lower = info("info depth 8 score cp 120 lowerbound nodes 900 time 3");
+        assert_eq!(lower.score, Some(Score::Cp(120)));
+        assert_eq!(lower.nodes, Some(900));
+        assert_eq!(lower.time, Some(3));
+        let upper = info("info score cp -40 upperbound depth 9");
+        assert_eq!(upper.score, Some(Score::Cp(-40)));
+        assert_eq!(upper.depth, Some(9));
+    }
+
+    #[test]
+    fn survives_bound_as_first_token() {
+        let info = info("info lowerbound depth 4");
+        assert_eq!(info.depth, Some(4));
+        assert_eq!(info.score, None);
+    }
+
+    #[test]
+    fn skips_values_of_unrelated_keywords() {
+        let info = info("info depth 15 currmove e2e4 currmovenumber 1 hashfull 12 tbhits 0 nodes 300");
+        assert_eq!(info.depth, Some(15));
+        assert_eq!(info.nodes, Some(300));
+        assert!(info.pv.is_empty());
+    }
+
+    #[test]
+    fn parses_multipv_and_pv() {
+        let info = info("info depth 20 multipv 2 score cp 10 wdl 300 600 100 pv e2e4 e7e5 g1f3");
+        assert_eq!(info.multipv, Some(2));
+        assert_eq!(info.wdl, Some(Wdl { win: 300, draw: 600, loss: 100 }));
+        let pv: Vec<Uci> = ["e2e4", "e7e5", "g1f3"].iter().map(|mov| mov.parse().unwrap()).collect();
+        assert_eq!(info.pv, pv);
+    }
+
+    #[test]
+    fn stops_at_string() {
+        let info = info("info depth 3 string NNUE evaluation using nn.nnue enabled");
+        assert_eq!(info.depth, Some(3));
+        assert!(info.pv.is_empty());
+    }
+}
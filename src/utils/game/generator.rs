@@ -0,0 +1,687 @@
+// Importing necessary modules and structures from the `std`, `rand`, `rocket`, `serde` and `shakmaty` crates.
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use rand::seq::SliceRandom;
+use rocket::async_trait;
+use serde::{Deserialize, Serialize};
+use shakmaty::{CastlingMode, Color, Move, Position};
+use shakmaty::variant::{Variant, VariantPosition};
+use shakmaty::uci::Uci;
+
+// Importing the internal result type, the game helpers, the engine pool and the built-in search.
+use crate::utils::errors::internal::InternalResult;
+use crate::utils::game::{DIFFICULTY, find_with_auto_promotion};
+use crate::utils::game::analysis::Analysis;
+use crate::utils::game::book::{BookConfig, BookMover, OpeningBooks};
+use crate::utils::game::engine::{EngineConfig, SessionOptions};
+use crate::utils::game::pool::{EnginePool, PooledGenerator};
+use crate::utils::game::search::Searcher;
+use crate::utils::game::tablebase::{EndgameTablebase, TablebaseMover};
+use crate::utils::game::thinking::ThinkingStream;
+use crate::utils::game::weakening::{CANDIDATES, WEAKEST_ELO, WeakenedMover};
+
+// Structure representing the remaining time and the increment of both sides in milliseconds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClockState {
+    pub white_ms: u64,
+    pub black_ms: u64,
+    pub white_inc_ms: u64,
+    pub black_inc_ms: u64,
+}
+
+// Structure representing the limits of a search, the search stops as soon as one of the given limits is reached.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SearchLimits {
+    pub depth: Option<u32>,
+    // Fixed time per move in milliseconds.
+    pub movetime: Option<u64>,
+    pub nodes: Option<u64>,
+    // The clock is not configured, it is taken from the running game.
+    #[serde(skip)]
+    pub clock: Option<ClockState>,
+    // The stream the engine reports its thinking to, which is taken from the running game as well.
+    #[serde(skip)]
+    pub thinking: Option<ThinkingStream>,
+}
+
+impl SearchLimits {
+    // Method to create `SearchLimits` which only limit the depth.
+    pub fn depth(depth: u32) -> Self {
+        SearchLimits {
+            depth: Some(depth),
+            ..SearchLimits::default()
+        }
+    }
+
+    // Method to add the clock of a running game to the limits.
+    pub fn with_clock(mut self, clock: Option<ClockState>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    // Method to add the thinking stream of a running game to the limits.
+    pub fn with_thinking(mut self, thinking: ThinkingStream) -> Self {
+        self.thinking = Some(thinking);
+        self
+    }
+
+    // Method to calculate how much time a search for the given side may use, `None` if the time is not limited.
+    pub fn time_budget(&self, turn: Color) -> Option<Duration> {
+        let clock_budget = self.clock.map(|clock| {
+            let remaining = turn.fold_wb(clock.white_ms, clock.black_ms);
+            let increment = turn.fold_wb(clock.white_inc_ms, clock.black_inc_ms);
+            // Plan with 30 more moves, but never use more than half of the remaining time
+            (remaining / 30 + increment * 3 / 4).min(remaining / 2)
+        });
+        let budget = match (self.movetime, clock_budget) {
+            (Some(movetime), Some(clock)) => Some(movetime.min(clock)),
+            (movetime, clock) => movetime.or(clock),
+        };
+        budget.map(Duration::from_millis)
+    }
+}
+
+// Structure representing the configured search limits of every difficulty and of analyses, it is read from the `search_limits` config key.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DifficultyLimits {
+    pub easy: SearchLimits,
+    pub medium: SearchLimits,
+    pub hard: SearchLimits,
+    pub adaptive: SearchLimits,
+    pub analysis: SearchLimits,
+    // Limits of every position of a post-game review.
+    pub review: SearchLimits,
+}
+
+impl Default for DifficultyLimits {
+    fn default() -> Self {
+        DifficultyLimits {
+            easy: SearchLimits::depth(DIFFICULTY::EASY.parse_depth()),
+            medium: SearchLimits::depth(DIFFICULTY::MEDIUM.parse_depth()),
+            // The search time of a fixed depth varies a lot between positions, so hard searches are capped by time as well
+            hard: SearchLimits {
+                movetime: Some(1500),
+                ..SearchLimits::depth(DIFFICULTY::HARD.parse_depth())
+            },
+            // The strength of adaptive games is limited by the elo only
+            adaptive: SearchLimits {
+                movetime: Some(1000),
+                ..SearchLimits::depth(DIFFICULTY::ADAPTIVE.parse_depth())
+            },
+            analysis: SearchLimits {
+                movetime: Some(2000),
+                ..SearchLimits::depth(20)
+            },
+            review: SearchLimits {
+                movetime: Some(500),
+                ..SearchLimits::depth(16)
+            },
+        }
+    }
+}
+
+impl DifficultyLimits {
+    // Method to get the search limits of a difficulty.
+    pub fn get(&self, difficulty: &DIFFICULTY) -> SearchLimits {
+        match difficulty {
+            DIFFICULTY::EASY => self.easy.clone(),
+            DIFFICULTY::MEDIUM => self.medium.clone(),
+            DIFFICULTY::HARD => self.hard.clone(),
+            DIFFICULTY::ADAPTIVE => self.adaptive.clone(),
+        }
+    }
+}
+
+// Enum representing the evaluation of a position from the view of the side to move.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Score {
+    // Advantage in centipawns.
+    Cp(i32),
+    // Moves until mate, negative if the side to move gets mated.
+    Mate(i32),
+}
+
+// Structure representing the win, draw and loss expectation in permill from the view of the side to move.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Wdl {
+    pub win: u32,
+    pub draw: u32,
+    pub loss: u32,
+}
+
+// Structure representing one principal variation of a search.
+#[derive(Clone, Debug)]
+pub struct PvLine {
+    pub multipv: u32,
+    pub depth: u32,
+    pub score: Score,
+    pub wdl: Option<Wdl>,
+    pub moves: Vec<Move>,
+}
+
+// Structure representing the outcome of a search.
+#[derive(Clone, Debug, Default)]
+pub struct SearchResult {
+    // `None` if there is no legal move in the searched position.
+    pub best_move: Option<Move>,
+    // The expected reply to the best move.
+    pub ponder: Option<Move>,
+    // The principal variations, ordered by their rank.
+    pub lines: Vec<PvLine>,
+    pub depth: u32,
+    pub seldepth: u32,
+    pub nodes: u64,
+    pub nps: u64,
+}
+
+impl SearchResult {
+    // Method to create a `SearchResult` that only knows the move to play.
+    pub fn from_move(mov: Move) -> Self {
+        SearchResult {
+            best_move: Some(mov),
+            ..SearchResult::default()
+        }
+    }
+}
+
+impl PvLine {
+    // Method to get the wdl of the engine, or an estimation out of the score if the engine did not send one.
+    pub fn estimated_wdl(&self) -> Wdl {
+        self.wdl.unwrap_or_else(|| Wdl::from_score(self.score))
+    }
+}
+
+impl Wdl {
+    // Function to estimate the wdl of a score, the win and the loss rate follow a logistic curve of the centipawns.
+    // The parameters are roughly the ones of the middlegame in the wdl model of stockfish, the rest is the draw rate.
+    pub fn from_score(score: Score) -> Self {
+        const CENTER: f64 = 330.;
+        const SPREAD: f64 = 70.;
+        match score {
+            Score::Mate(moves) if moves > 0 => Wdl { win: 1000, draw: 0, loss: 0 },
+            Score::Mate(_) => Wdl { win: 0, draw: 0, loss: 1000 },
+            Score::Cp(cp) => {
+                let rate = |cp: f64| (1000. / (1. + ((CENTER - cp) / SPREAD).exp())).round() as u32;
+                let (win, loss) = (rate(cp as f64), rate(-(cp as f64)));
+                Wdl { win, draw: 1000_u32.saturating_sub(win + loss), loss }
+            }
+        }
+    }
+}
+
+impl Score {
+    // Method to convert the score into centipawns, mates are worth more than any material and faster mates more than slower ones.
+    pub fn centipawns(&self) -> i32 {
+        match *self {
+            Score::Cp(cp) => cp,
+            Score::Mate(moves) if moves > 0 => 10_000 - moves * 10,
+            Score::Mate(moves) => -10_000 - moves * 10,
+        }
+    }
+
+    // Method to convert a score of the side to move into a score from the view of white.
+    pub fn white_view(self, turn: Color) -> Score {
+        match (self, turn) {
+            (score, Color::White) => score,
+            (Score::Cp(cp), Color::Black) => Score::Cp(-cp),
+            (Score::Mate(moves), Color::Black) => Score::Mate(-moves),
+        }
+    }
+}
+
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Score::Cp(cp) => write!(f, "cp {}", cp),
+            Score::Mate(moves) => write!(f, "mate {}", moves),
+        }
+    }
+}
+
+// Formats the result similar to the `info` output of an UCI engine, which is useful for logging.
+impl fmt::Display for SearchResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "depth {} seldepth {} nodes {} nps {}", self.depth, self.seldepth, self.nodes, self.nps)?;
+        for line in &self.lines {
+            write!(f, " | multipv {} depth {} score {}", line.multipv, line.depth, line.score)?;
+            if let Some(wdl) = line.wdl {
+                write!(f, " wdl {} {} {}", wdl.win, wdl.draw, wdl.loss)?;
+            }
+            write!(f, " pv")?;
+            for mov in &line.moves {
+                write!(f, " {}", mov.to_uci(CastlingMode::Standard))?;
+            }
+        }
+        if let Some(best_move) = &self.best_move {
+            write!(f, " | bestmove {}", best_move.to_uci(CastlingMode::Standard))?;
+        }
+        if let Some(ponder) = &self.ponder {
+            write!(f, " ponder {}", ponder.to_uci(CastlingMode::Standard))?;
+        }
+        Ok(())
+    }
+}
+
+// Trait for everything that is able to answer a position with a move.
+// A `Game` only talks to this trait, so the backend can be exchanged without touching the routes.
+#[async_trait]
+pub trait MoveGenerator: Send {
+    // Asynchronous method to search the given board for the side to move within the given limits.
+    async fn search(&mut self, board: &VariantPosition, limits: &SearchLimits) -> InternalResult<SearchResult>;
+
+    // Asynchronous method to generate the next move for the side to move on the given board.
+    async fn gen_next_move(&mut self, board: &VariantPosition, limits: &SearchLimits) -> InternalResult<Move> {
+        let result = self.search(board, limits).await?;
+        debug!("GENERATOR: {}", result);
+        result.best_move.ok_or("GENERATOR: There is no legal move left")
+    }
+}
+
+// Enum representing the move generator backends, which can be selected via the `generator` config key.
+#[derive(Clone, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GeneratorBackend {
+    // Every search checks out a stockfish process of the pool, the built-in search is used if stockfish is missing.
+    #[default]
+    Stockfish,
+    // Every game uses the built-in pure Rust search.
+    Builtin,
+    // Every move is picked randomly out of all legal moves.
+    Random,
+    // The moves are replayed in the given order, which is useful for integration tests.
+    Scripted(Vec<String>),
+}
+
+// Name of the engine of the `engine` section in the engine registry.
+pub const DEFAULT_ENGINE: &str = "default";
+
+// Structure representing a bot of the `bots` config key, which plays with one of the registered engines.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BotConfig {
+    pub name: String,
+    // Name of the engine in the engine registry, `default` is the engine of the `engine` section.
+    pub engine: String,
+    // The elo of the bot, which the score is based on.
+    // It is passed to the engine as `UCI_Elo` if the engine supports it, otherwise the engine plays at full strength within the limits.
+    // The launch fails if the elo is outside of the range of the engine.
+    pub elo: u16,
+    #[serde(default = "BotConfig::default_limits")]
+    pub limits: SearchLimits,
+}
+
+impl BotConfig {
+    // Bots think one second per move, if no limits are configured.
+    fn default_limits() -> SearchLimits {
+        SearchLimits {
+            movetime: Some(1000),
+            ..SearchLimits::default()
+        }
+    }
+}
+
+// Structure representing the configuration of the `GeneratorFactory`, which is read from the rocket config.
+pub struct GeneratorConfig {
+    pub backend: GeneratorBackend,
+    // Amount of stockfish processes of the engine pool.
+    pub pool_size: usize,
+    pub limits: DifficultyLimits,
+    // `None` if the engine section is missing, the default stockfish falls back to the built-in search then.
+    pub engine: Option<EngineConfig>,
+    // Further engines by their name, which bots can play with.
+    pub engines: BTreeMap<String, EngineConfig>,
+    pub bots: Vec<BotConfig>,
+    // Name of the engine in the engine registry, which plays the variants, e.g. Fairy-Stockfish.
+    pub variant_engine: Option<String>,
+    pub books: BookConfig,
+    // Directory of the Syzygy tables, which are used by HARD and the tablebase endpoint.
+    pub syzygy_path: Option<PathBuf>,
+}
+
+// The `GeneratorFactory` structure creates the move generator of every new game out of the configured backend.
+// Cloning it is cheap, the engine pool, the books and the tablebase are shared.
+#[derive(Clone)]
+pub struct GeneratorFactory {
+    backend: GeneratorBackend,
+    pool: Option<Arc<EnginePool>>,
+    // The pools of the registered engines by their name.
+    engines: BTreeMap<String, Arc<EnginePool>>,
+    bots: Arc<Vec<BotConfig>>,
+    // The engine pool of the variant engine, variants can not be played without it.
+    variant_pool: Option<Arc<EnginePool>>,
+    limits: DifficultyLimits,
+    books: OpeningBooks,
+    tablebase: Option<Arc<EndgameTablebase>>,
+}
+
+impl GeneratorFactory {
+    // Method to create a new `GeneratorFactory` for a given config, the stockfish backend starts an engine pool.
+    // Fails if one of the opening books or the tablebase can not be read, the engine rejects one of the configured options,
+    // a configured or registered engine can not be started, a bot is invalid or plays at an elo its engine does not support,
+    // or the variant engine does not play any variant.
+    pub async fn new(config: GeneratorConfig) -> InternalResult<Self> {
+        validate_bots(&config.bots, &config.engines)?;
+        let books = OpeningBooks::load(&config.books)?;
+        let tablebase = match &config.syzygy_path {
+            Some(path) => Some(Arc::new(EndgameTablebase::open(path)?)),
+            None => None
+        };
+        let pool = match config.backend {
+            GeneratorBackend::Stockfish => {
+                // Only the unconfigured default engine falls back, a configured engine was chosen on purpose
+                let configured = config.engine.is_some();
+                let engine = config.engine.unwrap_or_default();
+                let binary = engine.binary.display().to_string();
+                let pool = EnginePool::new(engine.pool_size.unwrap_or(config.pool_size), engine).await?;
+                match pool {
+                    None if configured => return Err("ENGINE: The configured engine could not be started"),
+                    None => warn!("Stockfish could not be started as {}, falling back to the built-in search", binary),
+                    Some(_) => {}
+                }
+                pool.map(Arc::new)
+            }
+            _ => None
+        };
+        // Unlike the default engine, a registered engine was chosen on purpose, so there is no fallback if it is missing
+        let mut engines = BTreeMap::new();
+        for (name, engine) in config.engines {
+            let pool = EnginePool::new(engine.pool_size.unwrap_or(1), engine).await?.ok_or("ENGINE-REGISTRY: A registered engine could not be started")?;
+            info!("ENGINE-REGISTRY: Registered engine {}", name);
+            engines.insert(name, Arc::new(pool));
+        }
+        let mut factory = GeneratorFactory {
+            backend: config.backend,
+            pool,
+            engines,
+            bots: Arc::new(config.bots),
+            variant_pool: None,
+            limits: config.limits,
+            books,
+            tablebase,
+        };
+        if let Some(name) = &config.variant_engine {
+            let pool = factory.engine_pool(name).ok_or("ENGINE-REGISTRY: The variant engine is not registered")?;
+            if pool.variants().is_empty() {
+                return Err("ENGINE-REGISTRY: The variant engine does not support any variant");
+            }
+            factory.variant_pool = Some(pool.clone());
+        }
+        factory.validate_bot_elos()?;
+        Ok(factory)
+    }
+
+    // Method to check that every bot plays at an elo its engine supports, a bot would play at full strength otherwise.
+    // Engines without an elo option are not checked, their bots always play at full strength within the limits.
+    fn validate_bot_elos(&self) -> InternalResult<()> {
+        for bot in self.bots.iter() {
+            let range = match bot.engine.as_str() {
+                DEFAULT_ENGINE => self.elo_range(),
+                name => self.engines.get(name).and_then(|pool| pool.elo_range()),
+            };
+            if range.is_some_and(|range| !range.contains(&bot.elo)) {
+                error!("ENGINE-REGISTRY: The elo {} of the bot {} is outside of the range of its engine", bot.elo, bot.name);
+                return Err("ENGINE-REGISTRY: The elo of a bot is not supported by its engine");
+            }
+        }
+        Ok(())
+    }
+
+    // Method to get the configured search limits of a difficulty.
+    pub fn limits(&self, difficulty: &DIFFICULTY) -> SearchLimits {
+        self.limits.get(difficulty)
+    }
+
+    // Method to get the engine pool, if the stockfish backend is running.
+    pub fn pool(&self) -> Option<&Arc<EnginePool>> {
+        self.pool.as_ref()
+    }
+
+    // Method to get the engine pool of an engine of the registry, `default` is the engine pool of the stockfish backend.
+    pub fn engine_pool(&self, name: &str) -> Option<&Arc<EnginePool>> {
+        match name {
+            DEFAULT_ENGINE => self.pool(),
+            _ => self.engines.get(name),
+        }
+    }
+
+    // Method to get the configured bots.
+    pub fn bots(&self) -> &[BotConfig] {
+        &self.bots
+    }
+
+    // Method to get a configured bot by its name.
+    pub fn bot(&self, name: &str) -> Option<&BotConfig> {
+        self.bots.iter().find(|bot| bot.name == name)
+    }
+
+    // Method to get the variants games can be played in besides standard chess.
+    pub fn variants(&self) -> &[Variant] {
+        self.variant_pool.as_ref().map_or(&[], |pool| pool.variants())
+    }
+
+    // Method to get the Syzygy tablebase, if it is configured.
+    pub fn tablebase(&self) -> Option<&Arc<EndgameTablebase>> {
+        self.tablebase.as_ref()
+    }
+
+    // Method to get the elo range games can be played at, `None` if the backend can not limit its strength.
+    // Games below the weakest elo of the engine are played by the weakening layer.
+    pub fn elo_range(&self) -> Option<RangeInclusive<u16>> {
+        let range = match self.backend {
+            GeneratorBackend::Stockfish => self.pool.as_ref()?.elo_range()?,
+            _ => return None,
+        };
+        Some(WEAKEST_ELO.min(*range.start())..=*range.end())
+    }
+
+    // Method to get the elo a game is played at, fails if the requested elo is not supported.
+    // Without a requested elo, the default elo is clamped to the range of the engine just like the engine would do it.
+    // Returns `None` if the engine can not limit its strength, e.g. the built-in search, so the elo of the game is unknown.
+    pub fn strength(&self, elo: Option<u16>, default: u16) -> InternalResult<Option<u16>> {
+        match (elo, self.elo_range()) {
+            (Some(elo), Some(range)) if range.contains(&elo) => Ok(Some(elo)),
+            (Some(_), Some(_)) => Err("GENERATOR: The elo is outside of the range of the engine"),
+            (Some(_), None) => Err("GENERATOR: The engine can not limit its strength"),
+            (None, Some(range)) => Ok(Some(default.clamp(*range.start(), *range.end()))),
+            (None, None) => Ok(None),
+        }
+    }
+
+    // Method to build a move generator playing at the given difficulty and elo, it plays out of the book of the difficulty first.
+    // HARD converts endgames perfectly if a tablebase is configured.
+    // With `ponder` the engine may think on the turn of the opponent, if pondering is configured.
+    pub fn build(&self, difficulty: &DIFFICULTY, elo: Option<u16>, ponder: bool) -> Option<Box<dyn MoveGenerator>> {
+        let mut generator = self.build_backend(elo, ponder)?;
+        if let (DIFFICULTY::HARD, Some(tablebase)) = (difficulty, &self.tablebase) {
+            generator = Box::new(TablebaseMover::new(tablebase.clone(), generator));
+        }
+        match self.books.get(difficulty) {
+            Some(book) => Some(Box::new(BookMover::new(book, generator))),
+            None => Some(generator),
+        }
+    }
+
+    // Method to build the move generator of a configured bot.
+    // Bots of the default engine are built like the difficulties, so they play below the weakest elo of the engine as well.
+    pub fn build_bot(&self, bot: &BotConfig, ponder: bool) -> Option<Box<dyn MoveGenerator>> {
+        if bot.engine == DEFAULT_ENGINE {
+            return self.build_backend(Some(bot.elo), ponder);
+        }
+        let pool = self.engines.get(&bot.engine)?;
+        // The elo of the bot is checked against the range of its engine at launch
+        let elo = pool.elo_range().map(|_| bot.elo);
+        Some(Box::new(PooledGenerator::new(pool.clone(), SessionOptions {
+            elo,
+            ..SessionOptions::default()
+        }, ponder)))
+    }
+
+    // Method to get the elo a variant game is played at, the elo is clamped to the range of the variant engine.
+    // Returns `None` if the variant engine has no elo option, it plays at full strength within the limits then.
+    pub fn variant_strength(&self, elo: u16) -> Option<u16> {
+        let range = self.variant_pool.as_ref()?.elo_range()?;
+        Some(elo.clamp(*range.start(), *range.end()))
+    }
+
+    // Method to build the move generator of a variant game, the elo is clamped to the range of the variant engine.
+    pub fn build_variant(&self, elo: Option<u16>, ponder: bool) -> Option<Box<dyn MoveGenerator>> {
+        let pool = self.variant_pool.as_ref()?;
+        let elo = elo.and_then(|elo| self.variant_strength(elo));
+        Some(Box::new(PooledGenerator::new(pool.clone(), SessionOptions {
+            elo,
+            ..SessionOptions::default()
+        }, ponder)))
+    }
+
+    // Method to build the move generator of the configured backend, without an elo the engine plays at full strength within the limits.
+    fn build_backend(&self, elo: Option<u16>, ponder: bool) -> Option<Box<dyn MoveGenerator>> {
+        match &self.backend {
+            GeneratorBackend::Stockfish => match &self.pool {
+                // Below its weakest elo the engine searches candidate moves at that elo, which the weakening layer picks from
+                Some(pool) => match (pool.elo_range(), elo) {
+                    (Some(range), Some(elo)) if elo < *range.start() => {
+                        let engine = PooledGenerator::new(pool.clone(), SessionOptions {
+                            elo: Some(*range.start()),
+                            multipv: Some(CANDIDATES),
+                        }, false);
+                        Some(Box::new(WeakenedMover::new(Box::new(engine), elo, *range.start())))
+                    }
+                    _ => Some(Box::new(PooledGenerator::new(pool.clone(), SessionOptions {
+                        elo,
+                        ..SessionOptions::default()
+                    }, ponder))),
+                },
+                None => Some(Box::new(Searcher)),
+            },
+            GeneratorBackend::Builtin => Some(Box::new(Searcher)),
+            GeneratorBackend::Random => Some(Box::new(RandomMover)),
+            GeneratorBackend::Scripted(moves) => Some(Box::new(ScriptedMover::new(moves)?)),
+        }
+    }
+
+    // Asynchronous method to search a position at full strength with the given amount of lines.
+    // The built-in search is used if there is no engine pool, it only reports the best line.
+    pub async fn search_full_strength(&self, board: &VariantPosition, multipv: u32) -> InternalResult<SearchResult> {
+        self.search_at_full_strength(board, multipv, &self.limits.analysis).await
+    }
+
+    // Asynchronous method to search a position of a post-game review at full strength.
+    pub async fn search_for_review(&self, board: &VariantPosition) -> InternalResult<SearchResult> {
+        self.search_at_full_strength(board, 1, &self.limits.review).await
+    }
+
+    // Positions of a variant are searched by the variant engine.
+    async fn search_at_full_strength(&self, board: &VariantPosition, multipv: u32, limits: &SearchLimits) -> InternalResult<SearchResult> {
+        let pool = match board.variant() {
+            Variant::Chess => self.pool.as_ref(),
+            _ => Some(self.variant_pool.as_ref().ok_or("GENERATOR: There is no engine for this variant")?),
+        };
+        let mut generator: Box<dyn MoveGenerator> = match pool {
+            Some(pool) => Box::new(PooledGenerator::new(pool.clone(), SessionOptions {
+                elo: None,
+                multipv: Some(multipv),
+            }, false)),
+            None => Box::new(Searcher),
+        };
+        generator.search(board, limits).await
+    }
+
+    // Asynchronous method to analyse a position at full strength with the given amount of lines.
+    pub async fn analyze(&self, board: &VariantPosition, multipv: u32) -> InternalResult<Analysis> {
+        let result = self.search_full_strength(board, multipv).await?;
+        Ok(Analysis::new(board, result))
+    }
+}
+
+// Function to check the bots before any engine is started, every bot needs a unique name, a registered engine and a search limit.
+fn validate_bots(bots: &[BotConfig], engines: &BTreeMap<String, EngineConfig>) -> InternalResult<()> {
+    if engines.contains_key(DEFAULT_ENGINE) {
+        return Err("ENGINE-REGISTRY: The name default is reserved for the engine section");
+    }
+    for (index, bot) in bots.iter().enumerate() {
+        if bot.name.trim().is_empty() {
+            return Err("ENGINE-REGISTRY: Bot names must not be empty");
+        }
+        if bots[..index].iter().any(|other| other.name == bot.name) {
+            return Err("ENGINE-REGISTRY: Bot names must be unique");
+        }
+        if bot.engine != DEFAULT_ENGINE && !engines.contains_key(&bot.engine) {
+            return Err("ENGINE-REGISTRY: A bot references an engine that is not registered");
+        }
+        if bot.limits.depth.is_none() && bot.limits.movetime.is_none() && bot.limits.nodes.is_none() {
+            return Err("ENGINE-REGISTRY: Every bot needs a depth, movetime or nodes limit");
+        }
+    }
+    Ok(())
+}
+
+// The `RandomMover` structure plays a random legal move.
+pub struct RandomMover;
+
+#[async_trait]
+impl MoveGenerator for RandomMover {
+    async fn search(&mut self, board: &VariantPosition, _limits: &SearchLimits) -> InternalResult<SearchResult> {
+        let moves = board.legal_moves();
+        let mov = moves.choose(&mut rand::thread_rng()).ok_or("RANDOM: There is no legal move left")?;
+        Ok(SearchResult::from_move(mov.clone()))
+    }
+}
+
+// The `ScriptedMover` structure replays a fixed list of moves in UCI notation.
+pub struct ScriptedMover {
+    moves: VecDeque<Uci>,
+}
+
+impl ScriptedMover {
+    // Method to create a new `ScriptedMover`, returns `None` if one of the moves is no valid UCI.
+    pub fn new(moves: &[String]) -> Option<Self> {
+        let moves = moves.iter().map(|mov| mov.parse().ok()).collect::<Option<VecDeque<Uci>>>()?;
+        Some(ScriptedMover { moves })
+    }
+}
+
+#[async_trait]
+impl MoveGenerator for ScriptedMover {
+    async fn search(&mut self, board: &VariantPosition, _limits: &SearchLimits) -> InternalResult<SearchResult> {
+        let uci = self.moves.pop_front().ok_or("SCRIPTED: The script has no moves left")?;
+        let mov = find_with_auto_promotion(&uci, board).ok_or("SCRIPTED: The scripted move is not valid")?;
+        Ok(SearchResult::from_move(mov))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimates_wdl_from_score() {
+        let equal = Wdl::from_score(Score::Cp(0));
+        assert_eq!(equal.win, equal.loss);
+        assert!(equal.draw > 900, "{equal:?}");
+        let better = Wdl::from_score(Score::Cp(300));
+        assert!(better.win > better.loss && better.win < 1000, "{better:?}");
+        assert_eq!(Wdl::from_score(Score::Cp(-300)), Wdl { win: better.loss, draw: better.draw, loss: better.win });
+        assert_eq!(Wdl::from_score(Score::Cp(5000)).win, 1000);
+        for cp in (-2000..=2000).step_by(50) {
+            let wdl = Wdl::from_score(Score::Cp(cp));
+            assert_eq!(wdl.win + wdl.draw + wdl.loss, 1000, "{wdl:?}");
+        }
+        assert_eq!(Wdl::from_score(Score::Mate(3)), Wdl { win: 1000, draw: 0, loss: 0 });
+        assert_eq!(Wdl::from_score(Score::Mate(-1)), Wdl { win: 0, draw: 0, loss: 1000 });
+    }
+
+    #[test]
+    fn prefers_the_wdl_of_the_engine() {
+        let mut line = PvLine { multipv: 1, depth: 10, score: Score::Cp(0), wdl: Some(Wdl { win: 400, draw: 500, loss: 100 }), moves: Vec::new() };
+        assert_eq!(line.estimated_wdl(), Wdl { win: 400, draw: 500, loss: 100 });
+        line.wdl = None;
+        assert_eq!(line.estimated_wdl(), Wdl::from_score(Score::Cp(0)));
+    }
+}
@@ -0,0 +1,365 @@
+// Importing necessary modules and structures from the `std`, `rocket`, `serde`, `shakmaty` and `tokio` crates.
+use std::collections::BTreeMap;
+use std::ops::{Deref, DerefMut, RangeInclusive};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use rocket::async_trait;
+use serde::Serialize;
+use shakmaty::Position;
+use shakmaty::variant::{Variant, VariantPosition};
+use tokio::runtime::Handle;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+// Importing the internal result type, the stockfish `Engine` and the `MoveGenerator` trait.
+use crate::utils::errors::internal::InternalResult;
+use crate::utils::game::engine::{Engine, EngineConfig, SessionOptions};
+use crate::utils::game::generator::{MoveGenerator, SearchLimits, SearchResult};
+
+// Time an engine ponders for an idle game, afterwards it is stopped and the game checks out an engine for its next search.
+const PONDER_TIMEOUT: Duration = Duration::from_secs(120);
+
+// The `EnginePool` structure holds a bounded amount of warm stockfish processes, which are shared by all sessions.
+pub struct EnginePool {
+    // Engines that are currently not searching.
+    idle: Mutex<Vec<Engine>>,
+    // Every checked out engine holds one permit, so no more than `capacity` engines exist at once.
+    permits: Arc<Semaphore>,
+    capacity: usize,
+    metrics: PoolMetrics,
+    config: Arc<EngineConfig>,
+    // The elo range the engines reported, `None` if they can not limit their strength.
+    elo_range: Option<RangeInclusive<u16>>,
+    // The variants the engines reported besides standard chess.
+    variants: Vec<Variant>,
+    // Engines that ponder for a game by the id of its ticket, they are taken back as soon as another search waits.
+    pondering: Mutex<BTreeMap<u64, PooledEngine>>,
+    next_ticket: AtomicU64,
+}
+
+// The `PoolMetrics` structure counts the checkouts, so saturation of the pool becomes visible.
+#[derive(Default)]
+struct PoolMetrics {
+    waiting: AtomicUsize,
+    checkouts: AtomicU64,
+    saturated_checkouts: AtomicU64,
+    spawned: AtomicU64,
+    discarded: AtomicU64,
+    total_wait_micros: AtomicU64,
+    max_wait_micros: AtomicU64,
+}
+
+// The `PoolStats` structure is a serializable snapshot of the pool state and its queue metrics.
+#[derive(Serialize)]
+pub struct PoolStats {
+    pub capacity: usize,
+    pub idle: usize,
+    pub in_use: usize,
+    pub waiting: usize,
+    pub checkouts: u64,
+    pub saturated_checkouts: u64,
+    pub spawned: u64,
+    pub discarded: u64,
+    pub average_wait_ms: f64,
+    pub max_wait_ms: f64,
+}
+
+impl EnginePool {
+    // Method to create a new `EnginePool` and warm up `capacity` engines with the given config.
+    // Returns `None` if no engine could be started and an error if the engine rejects the config.
+    pub async fn new(capacity: usize, config: EngineConfig) -> InternalResult<Option<Self>> {
+        let capacity = capacity.max(1);
+        let config = Arc::new(config);
+        let mut idle = Vec::with_capacity(capacity);
+        while idle.len() < capacity {
+            match Engine::new(config.clone()).await {
+                Some(mut engine) => {
+                    engine.apply_config().await?;
+                    idle.push(engine);
+                }
+                None => break,
+            }
+        }
+        let Some(engine) = idle.first() else {
+            return Ok(None);
+        };
+        info!("ENGINE-POOL: Started {} processes of {}", idle.len(), engine.name().unwrap_or("unknown engine"));
+        let elo_range = engine.elo_range();
+        let variants = engine.variants();
+        let metrics = PoolMetrics::default();
+        metrics.spawned.store(idle.len() as u64, Ordering::Relaxed);
+        Ok(Some(EnginePool {
+            idle: Mutex::new(idle),
+            permits: Arc::new(Semaphore::new(capacity)),
+            capacity,
+            metrics,
+            config,
+            elo_range,
+            variants,
+            pondering: Mutex::new(BTreeMap::new()),
+            next_ticket: AtomicU64::new(0),
+        }))
+    }
+
+    // Asynchronous method to check out an engine, waits in the queue if all engines are searching.
+    pub async fn checkout(self: &Arc<Self>) -> InternalResult<PooledEngine> {
+        let start = Instant::now();
+        if self.permits.available_permits() == 0 {
+            self.metrics.saturated_checkouts.fetch_add(1, Ordering::Relaxed);
+            warn!("ENGINE-POOL: All {} engines are busy, queueing search", self.capacity);
+        }
+        self.metrics.waiting.fetch_add(1, Ordering::Relaxed);
+        // A search never waits for a game that only ponders, its engine is stopped and the permit is released
+        if self.permits.available_permits() == 0 {
+            self.preempt();
+        }
+        let permit = self.permits.clone().acquire_owned().await;
+        self.metrics.waiting.fetch_sub(1, Ordering::Relaxed);
+        let permit = permit.map_err(|_| "ENGINE-POOL: The pool has been closed")?;
+
+        let waited = start.elapsed().as_micros() as u64;
+        self.metrics.checkouts.fetch_add(1, Ordering::Relaxed);
+        self.metrics.total_wait_micros.fetch_add(waited, Ordering::Relaxed);
+        self.metrics.max_wait_micros.fetch_max(waited, Ordering::Relaxed);
+
+        // Engines that have been discarded after a failure are replaced on demand
+        let engine = self.idle.lock().map_err(|_| "ENGINE-POOL: Could not access idle engines")?.pop();
+        let engine = match engine {
+            Some(engine) => engine,
+            None => {
+                let mut engine = Engine::new(self.config.clone()).await.ok_or("ENGINE-POOL: Could not spawn engine")?;
+                engine.apply_config().await?;
+                self.metrics.spawned.fetch_add(1, Ordering::Relaxed);
+                engine
+            }
+        };
+        Ok(PooledEngine {
+            engine: Some(engine),
+            pool: self.clone(),
+            permit: Some(permit),
+        })
+    }
+
+    // Method to get the elo range the engines can be limited to.
+    pub fn elo_range(&self) -> Option<RangeInclusive<u16>> {
+        self.elo_range.clone()
+    }
+
+    // Method to get the variants the engines are able to play besides standard chess.
+    pub fn variants(&self) -> &[Variant] {
+        &self.variants
+    }
+
+    // Method to check if a game may keep an engine to ponder, which is only the case while no other search has to wait for it.
+    pub fn can_ponder(&self) -> bool {
+        self.config.ponder && self.permits.available_permits() > 0 && self.metrics.waiting.load(Ordering::Relaxed) == 0
+    }
+
+    // Method to keep an engine pondering for a game, returns the ticket the game takes it back with.
+    // Returns `None` and stops the engine, if another search is already waiting for an engine.
+    fn keep_pondering(self: &Arc<Self>, engine: PooledEngine) -> Option<PonderTicket> {
+        let mut pondering = self.pondering.lock().ok()?;
+        if self.metrics.waiting.load(Ordering::Relaxed) > 0 {
+            return None;
+        }
+        let id = self.next_ticket.fetch_add(1, Ordering::Relaxed);
+        pondering.insert(id, engine);
+        drop(pondering);
+        // A game that stays idle, e.g. because the user left it, loses its engine after the ponder timeout
+        let pool = Arc::downgrade(self);
+        tokio::spawn(async move {
+            tokio::time::sleep(PONDER_TIMEOUT).await;
+            if let Some(pool) = pool.upgrade() {
+                if pool.take_pondering(id).is_some() {
+                    info!("ENGINE-POOL: Stopped pondering of an idle game");
+                }
+            }
+        });
+        Some(PonderTicket { id, pool: self.clone() })
+    }
+
+    // Method to take back the pondering engine of a ticket, `None` if it has been preempted or timed out.
+    fn take_pondering(&self, id: u64) -> Option<PooledEngine> {
+        self.pondering.lock().ok()?.remove(&id)
+    }
+
+    // Method to stop the engine that ponders the longest, which releases its permit once it stopped.
+    fn preempt(&self) {
+        let engine = self.pondering.lock().ok().and_then(|mut pondering| pondering.pop_first()).map(|(_, engine)| engine);
+        if engine.is_some() {
+            info!("ENGINE-POOL: Stopped pondering for a waiting search");
+        }
+    }
+
+    // Method to take a snapshot of the pool metrics.
+    pub fn stats(&self) -> PoolStats {
+        let idle = self.idle.lock().map(|idle| idle.len()).unwrap_or(0);
+        let checkouts = self.metrics.checkouts.load(Ordering::Relaxed);
+        let total_wait = self.metrics.total_wait_micros.load(Ordering::Relaxed) as f64 / 1000.;
+        PoolStats {
+            capacity: self.capacity,
+            idle,
+            in_use: self.capacity - self.permits.available_permits(),
+            waiting: self.metrics.waiting.load(Ordering::Relaxed),
+            checkouts,
+            saturated_checkouts: self.metrics.saturated_checkouts.load(Ordering::Relaxed),
+            spawned: self.metrics.spawned.load(Ordering::Relaxed),
+            discarded: self.metrics.discarded.load(Ordering::Relaxed),
+            average_wait_ms: if checkouts == 0 { 0. } else { total_wait / checkouts as f64 },
+            max_wait_ms: self.metrics.max_wait_micros.load(Ordering::Relaxed) as f64 / 1000.,
+        }
+    }
+
+    // Method to give an engine back to the idle engines.
+    fn release(&self, engine: Engine) {
+        if let Ok(mut idle) = self.idle.lock() {
+            idle.push(engine);
+        }
+    }
+}
+
+// The `PooledEngine` structure is a checked out engine, which is returned to its pool once it is dropped.
+pub struct PooledEngine {
+    engine: Option<Engine>,
+    pool: Arc<EnginePool>,
+    // The permit is held until the engine is back in the pool.
+    permit: Option<OwnedSemaphorePermit>,
+}
+
+impl PooledEngine {
+    // Method to drop the engine instead of returning it, e.g. because it is in an unknown state after an error.
+    pub fn discard(mut self) {
+        self.engine.take();
+        self.pool.metrics.discarded.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl Deref for PooledEngine {
+    type Target = Engine;
+
+    fn deref(&self) -> &Engine {
+        self.engine.as_ref().expect("ENGINE-POOL: Engine used after discard")
+    }
+}
+
+impl DerefMut for PooledEngine {
+    fn deref_mut(&mut self) -> &mut Engine {
+        self.engine.as_mut().expect("ENGINE-POOL: Engine used after discard")
+    }
+}
+
+impl Drop for PooledEngine {
+    fn drop(&mut self) {
+        let Some(mut engine) = self.engine.take() else {
+            return;
+        };
+        if !engine.is_pondering() {
+            self.pool.release(engine);
+            return;
+        }
+        // A pondering engine has to be stopped before it is returned, which needs the async runtime
+        let pool = self.pool.clone();
+        let permit = self.permit.take();
+        match Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn(async move {
+                    match engine.stop_pondering().await {
+                        Ok(_) => pool.release(engine),
+                        Err(_) => {
+                            pool.metrics.discarded.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                    drop(permit);
+                });
+            }
+            Err(_) => {
+                pool.metrics.discarded.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+// The `PonderTicket` structure is the claim of a generator on the engine that ponders for it in the pool.
+// The engine is stopped once the ticket is dropped, e.g. together with the game.
+struct PonderTicket {
+    id: u64,
+    pool: Arc<EnginePool>,
+}
+
+impl PonderTicket {
+    // Method to take back the pondering engine, `None` if the pool needed it for another search in the meantime.
+    fn take(&self) -> Option<PooledEngine> {
+        self.pool.take_pondering(self.id)
+    }
+}
+
+impl Drop for PonderTicket {
+    fn drop(&mut self) {
+        self.take();
+    }
+}
+
+// The `PooledGenerator` structure checks out an engine of the pool for every single search.
+// With pondering the engine is kept after a search and thinks on the expected reply until the next search.
+pub struct PooledGenerator {
+    pool: Arc<EnginePool>,
+    session: SessionOptions,
+    ponder: bool,
+    // The ticket of the engine that is pondering for this generator.
+    pondering: Option<PonderTicket>,
+}
+
+impl PooledGenerator {
+    // Method to create a new `PooledGenerator`, which searches with the given session options.
+    // Pondering only makes sense if the generator plays the best move of the engine in a running game.
+    pub fn new(pool: Arc<EnginePool>, session: SessionOptions, ponder: bool) -> Self {
+        PooledGenerator { pool, session, ponder, pondering: None }
+    }
+
+    // Asynchronous method to let the engine ponder on the position after the best move and the expected reply.
+    // Returns the ticket of the engine, if it is pondering.
+    async fn start_pondering(&self, mut engine: PooledEngine, board: &VariantPosition, limits: &SearchLimits, result: &SearchResult) -> Option<PonderTicket> {
+        if !self.ponder || !self.pool.can_ponder() {
+            return None;
+        }
+        let mut expected = board.clone();
+        expected.play_unchecked(result.best_move.as_ref()?);
+        expected.play_unchecked(result.ponder.as_ref()?);
+        if expected.is_game_over() {
+            return None;
+        }
+        match engine.ponder(&expected, limits).await {
+            Ok(_) => self.pool.keep_pondering(engine),
+            Err(err) => {
+                warn!("ENGINE-POOL: Could not start pondering: {}", err);
+                engine.discard();
+                None
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl MoveGenerator for PooledGenerator {
+    async fn search(&mut self, board: &VariantPosition, limits: &SearchLimits) -> InternalResult<SearchResult> {
+        // A pondering engine is already configured for this generator, unless the pool took it back in the meantime
+        let (mut engine, configured) = match self.pondering.take().and_then(|ticket| ticket.take()) {
+            Some(engine) => (engine, Ok(())),
+            None => {
+                let mut engine = self.pool.checkout().await?;
+                // The engine might have searched for another session before, so it has to be reset first
+                let configured = engine.configure(self.session).await;
+                (engine, configured)
+            }
+        };
+        let result = match configured {
+            Ok(_) => engine.search(board, limits).await,
+            Err(err) => Err(err),
+        };
+        match &result {
+            Ok(result) => self.pondering = self.start_pondering(engine, board, limits, result).await,
+            Err(_) => engine.discard(),
+        }
+        result
+    }
+}
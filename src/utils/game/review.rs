@@ -0,0 +1,360 @@
+// Importing necessary modules and structures from the `serde` and `shakmaty` crates.
+use serde::Serialize;
+use shakmaty::{Color, Move, Outcome, Position};
+use shakmaty::variant::VariantPosition;
+use shakmaty::san::SanPlus;
+
+// Importing the internal result type and the search result types.
+use crate::utils::errors::internal::InternalResult;
+use crate::utils::game::generator::{GeneratorFactory, Score};
+
+// Evaluations are capped, so a won position that stays won is no mistake even if it wins less.
+const MAX_CENTIPAWNS: i32 = 1000;
+
+// Enum representing the quality of a move by its centipawn loss.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Classification {
+    // The move of the engine or one that is just as good.
+    Best,
+    Good,
+    // Loses at least 50 centipawns.
+    Inaccuracy,
+    // Loses at least 100 centipawns.
+    Mistake,
+    // Loses at least 300 centipawns.
+    Blunder,
+}
+
+// Structure representing a move in SAN and UCI notation.
+#[derive(Clone, Debug, Serialize)]
+pub struct ReviewMove {
+    pub san: String,
+    pub uci: String,
+}
+
+// Structure representing a reviewed move of a game, the scores are from the view of white.
+// The scores are `None` if the position could not be evaluated, the move is not classified then.
+#[derive(Clone, Debug, Serialize)]
+pub struct ReviewedMove {
+    // Number of the move in plies, starting at 1.
+    pub ply: u32,
+    // `white` or `black`.
+    pub color: &'static str,
+    #[serde(flatten)]
+    pub mov: ReviewMove,
+    // The evaluation of the position before and after the move.
+    pub score_before: Option<Score>,
+    pub score_after: Option<Score>,
+    pub cp_loss: Option<u32>,
+    // The move the engine would have played, `None` if it is the played move.
+    pub best: Option<ReviewMove>,
+    // Only the moves of the user are classified.
+    pub classification: Option<Classification>,
+}
+
+// Structure representing how accurately one side played.
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct SideAccuracy {
+    // Accuracy in percent, based on how much winning chance every move lost.
+    pub accuracy: f64,
+    // Average centipawn loss.
+    pub acpl: f64,
+}
+
+// Structure representing the accuracy of both sides of a game.
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct GameAccuracy {
+    pub white: SideAccuracy,
+    pub black: SideAccuracy,
+}
+
+// Structure representing the review of a finished game.
+#[derive(Clone, Debug, Serialize)]
+pub struct GameReview {
+    pub accuracy: GameAccuracy,
+    pub moves: Vec<ReviewedMove>,
+}
+
+// Structure representing the evaluation of a position from the view of the side to move.
+struct Evaluation {
+    score: Score,
+    best: Option<Move>,
+}
+
+impl Classification {
+    // Method to classify a move by its centipawn loss.
+    pub fn new(cp_loss: u32, best: bool) -> Self {
+        if best {
+            return Classification::Best;
+        }
+        match cp_loss {
+            0 => Classification::Best,
+            1..=49 => Classification::Good,
+            50..=99 => Classification::Inaccuracy,
+            100..=299 => Classification::Mistake,
+            _ => Classification::Blunder,
+        }
+    }
+}
+
+impl ReviewMove {
+    // Method to create a `ReviewMove` out of a move on the given board.
+    fn new(board: &VariantPosition, mov: &Move) -> Self {
+        ReviewMove {
+            san: SanPlus::from_move(board.clone(), mov).to_string(),
+            uci: mov.to_uci(board.castles().mode()).to_string(),
+        }
+    }
+}
+
+impl Evaluation {
+    // Method to get the capped evaluation in centipawns from the view of white.
+    fn white_centipawns(&self, turn: Color) -> i32 {
+        let cp = self.score.centipawns().clamp(-MAX_CENTIPAWNS, MAX_CENTIPAWNS);
+        turn.fold_wb(cp, -cp)
+    }
+}
+
+// Asynchronous function to review every move of a game, which is replayed from its start position.
+// Every position is searched at full strength, so the review takes a while; a position whose search failed is missing in the review.
+pub async fn review_game(factory: &GeneratorFactory, start: &VariantPosition, moves: &[Move], user: Color) -> InternalResult<GameReview> {
+    let mut position = start.clone();
+    let mut evaluations = Vec::with_capacity(moves.len() + 1);
+    for mov in moves.iter().map(Some).chain(std::iter::once(None)) {
+        let evaluation = evaluate(factory, &position).await;
+        evaluations.push(evaluation.inspect_err(|err| warn!("REVIEW: A position could not be evaluated: {}", err)).ok());
+        if let Some(mov) = mov {
+            position.play_unchecked(mov);
+        }
+    }
+    if evaluations.iter().all(Option::is_none) {
+        return Err("REVIEW: No position could be evaluated");
+    }
+    Ok(review_moves(start, moves, &evaluations, user))
+}
+
+// Function to review every move out of the evaluation of the start position and of the position after every move.
+fn review_moves(start: &VariantPosition, moves: &[Move], evaluations: &[Option<Evaluation>], user: Color) -> GameReview {
+    let mut position = start.clone();
+    let mut reviewed = Vec::with_capacity(moves.len());
+    // The evaluation of every position from the view of white, which the accuracy is calculated from
+    let mut white_cps = vec![evaluations[0].as_ref().map(|evaluation| evaluation.white_centipawns(position.turn()))];
+    for (index, (mov, pair)) in moves.iter().zip(evaluations.windows(2)).enumerate() {
+        let (evaluation, next) = (pair[0].as_ref(), pair[1].as_ref());
+        let mover = position.turn();
+        let mut after = position.clone();
+        after.play_unchecked(mov);
+
+        // Both evaluations are compared from the view of the side that moved
+        let before_cp = evaluation.map(|evaluation| evaluation.white_centipawns(mover));
+        let after_cp = next.map(|next| next.white_centipawns(after.turn()));
+        white_cps.push(after_cp);
+        let cp_loss = before_cp.zip(after_cp).map(|(before_cp, after_cp)| mover.fold_wb(before_cp - after_cp, after_cp - before_cp).max(0) as u32);
+        let best = evaluation.and_then(|evaluation| evaluation.best.as_ref());
+        let is_best = best == Some(mov);
+
+        reviewed.push(ReviewedMove {
+            ply: index as u32 + 1,
+            color: mover.fold_wb("white", "black"),
+            mov: ReviewMove::new(&position, mov),
+            score_before: evaluation.map(|evaluation| evaluation.score.white_view(mover)),
+            score_after: next.map(|next| next.score.white_view(after.turn())),
+            cp_loss,
+            best: best.filter(|_| !is_best).map(|best| ReviewMove::new(&position, best)),
+            classification: cp_loss.filter(|_| mover == user).map(|cp_loss| Classification::new(cp_loss, is_best)),
+        });
+        position = after;
+    }
+    GameReview {
+        accuracy: game_accuracy(&white_cps, &reviewed, start.turn()),
+        moves: reviewed,
+    }
+}
+
+// Asynchronous function to evaluate a position, positions at the end of the game are not searched.
+async fn evaluate(factory: &GeneratorFactory, board: &VariantPosition) -> InternalResult<Evaluation> {
+    if let Some(score) = outcome_score(board) {
+        return Ok(Evaluation { score, best: None });
+    }
+    let result = factory.search_for_review(board).await?;
+    let score = result.lines.first().map(|line| line.score).ok_or("REVIEW: The search did not report a score")?;
+    Ok(Evaluation { score, best: result.best_move })
+}
+
+// Function to get the score of a finished game from the view of the side to move, `None` if the game is not finished.
+// In variants like Antichess the side to move can be the winner, there is no mate score for that so it gets the capped maximum.
+fn outcome_score(board: &VariantPosition) -> Option<Score> {
+    match board.outcome()? {
+        Outcome::Decisive { winner } if winner == board.turn() => Some(Score::Cp(MAX_CENTIPAWNS)),
+        Outcome::Decisive { .. } => Some(Score::Mate(0)),
+        Outcome::Draw => Some(Score::Cp(0)),
+    }
+}
+
+// Function to convert centipawns into the winning chance in percent, like Lichess does.
+fn win_percent(cp: i32) -> f64 {
+    50. + 50. * (2. / (1. + (-0.00368208 * f64::from(cp)).exp()) - 1.)
+}
+
+// Function to get the accuracy of a single move out of the winning chances of the mover before and after it, like Lichess does.
+fn move_accuracy(win_before: f64, win_after: f64) -> f64 {
+    if win_after >= win_before {
+        return 100.;
+    }
+    (103.1668100711649 * (-0.04354415386753951 * (win_before - win_after)).exp() - 3.166924740191411 + 1.).clamp(0., 100.)
+}
+
+// Function to calculate the accuracy of both sides with the Lichess formula.
+// The accuracy of every move is weighted by the volatility of the game around it, the result is the mean of the weighted and the harmonic mean.
+// Moves next to a position that could not be evaluated are left out.
+fn game_accuracy(white_cps: &[Option<i32>], moves: &[ReviewedMove], first: Color) -> GameAccuracy {
+    let wins: Vec<Option<f64>> = white_cps.iter().map(|cp| cp.map(win_percent)).collect();
+    let window_size = (wins.len() / 10).clamp(2, 8);
+    // The first moves are weighted by the first window, so there is one window per move
+    let first_window = &wins[..window_size.min(wins.len())];
+    let weights: Vec<f64> = std::iter::repeat_n(first_window, window_size.saturating_sub(2))
+        .chain(wins.windows(window_size))
+        .map(|window| standard_deviation(&window.iter().flatten().copied().collect::<Vec<f64>>()).clamp(0.5, 12.))
+        .collect();
+
+    let mut sides = [(Vec::new(), Vec::new()), (Vec::new(), Vec::new())];
+    let mut mover = first;
+    for (index, pair) in wins.windows(2).enumerate() {
+        if let (Some(before), Some(after)) = (pair[0], pair[1]) {
+            let (before, after) = mover.fold_wb((before, after), (100. - before, 100. - after));
+            let side = &mut sides[mover.fold_wb(0, 1)];
+            side.0.push(move_accuracy(before, after));
+            side.1.push(weights.get(index).copied().unwrap_or(0.5));
+        }
+        mover = !mover;
+    }
+    let acpl = |color: Color| {
+        let losses: Vec<f64> = moves.iter().filter(|mov| mov.color == color.fold_wb("white", "black")).filter_map(|mov| mov.cp_loss.map(f64::from)).collect();
+        if losses.is_empty() { 0. } else { round(losses.iter().sum::<f64>() / losses.len() as f64) }
+    };
+    let [white, black] = sides;
+    GameAccuracy {
+        white: SideAccuracy { accuracy: side_accuracy(&white.0, &white.1), acpl: acpl(Color::White) },
+        black: SideAccuracy { accuracy: side_accuracy(&black.0, &black.1), acpl: acpl(Color::Black) },
+    }
+}
+
+// Function to combine the accuracies of the moves of one side, a side without moves played perfectly.
+fn side_accuracy(accuracies: &[f64], weights: &[f64]) -> f64 {
+    if accuracies.is_empty() {
+        return 100.;
+    }
+    let weighted = accuracies.iter().zip(weights).map(|(accuracy, weight)| accuracy * weight).sum::<f64>() / weights.iter().sum::<f64>();
+    let harmonic = accuracies.len() as f64 / accuracies.iter().map(|accuracy| 1. / accuracy).sum::<f64>();
+    round((weighted + harmonic) / 2.)
+}
+
+fn standard_deviation(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.;
+    }
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    (values.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / values.len() as f64).sqrt()
+}
+
+// Function to round a value to one decimal.
+fn round(value: f64) -> f64 {
+    (value * 10.).round() / 10.
+}
+
+#[cfg(test)]
+mod tests {
+    use shakmaty::CastlingMode;
+    use shakmaty::fen::Fen;
+    use shakmaty::variant::Variant;
+    use super::*;
+
+    // Function to build a reviewed move, only the color and the centipawn loss matter for the accuracy.
+    fn reviewed(color: &'static str, cp_loss: Option<u32>) -> ReviewedMove {
+        ReviewedMove {
+            ply: 1,
+            color,
+            mov: ReviewMove { san: String::new(), uci: String::new() },
+            score_before: None,
+            score_after: None,
+            cp_loss,
+            best: None,
+            classification: None,
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-3, "{actual} is not {expected}");
+    }
+
+    // Function to build a position of a variant out of its FEN.
+    fn position(variant: Variant, fen: &str) -> VariantPosition {
+        let setup = fen.parse::<Fen>().unwrap().into_setup();
+        VariantPosition::from_setup(variant, setup, CastlingMode::Standard).unwrap()
+    }
+
+    #[test]
+    fn scores_finished_games_for_the_winner() {
+        // White is mated
+        let mated = position(Variant::Chess, "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3");
+        assert_eq!(outcome_score(&mated), Some(Score::Mate(0)));
+        assert_eq!(Evaluation { score: Score::Mate(0), best: None }.white_centipawns(Color::White), -MAX_CENTIPAWNS);
+        // White has lost all pieces and wins the Antichess game on its turn
+        let antichess = position(Variant::Antichess, "8/8/8/8/8/8/8/k7 w - - 0 1");
+        assert_eq!(outcome_score(&antichess), Some(Score::Cp(MAX_CENTIPAWNS)));
+        assert_eq!(Evaluation { score: Score::Cp(MAX_CENTIPAWNS), best: None }.white_centipawns(Color::White), MAX_CENTIPAWNS);
+        assert_eq!(outcome_score(&VariantPosition::new(Variant::Antichess)), None);
+    }
+
+    #[test]
+    fn converts_centipawns_into_win_percent() {
+        assert_close(win_percent(0), 50.);
+        assert_close(win_percent(100), 59.1026);
+        assert_close(win_percent(-100), 40.8974);
+        assert_close(win_percent(300), 75.1126);
+        assert_close(win_percent(1000), 97.5447);
+    }
+
+    #[test]
+    fn caps_move_accuracy_at_100() {
+        // The formula adds 1, so a tiny loss of winning chance would be above 100 otherwise
+        assert_eq!(move_accuracy(50., 49.9), 100.);
+        assert_eq!(move_accuracy(40., 60.), 100.);
+        assert_close(move_accuracy(60., 40.), 41.0168);
+        assert_close(move_accuracy(80., 20.), 5.3993);
+    }
+
+    #[test]
+    fn weights_accuracy_by_volatility() {
+        // Win percentages 50, 40.9, 59.1, 24.9 and 50, the windows of two positions weight the moves with 4.55, 9.10, 12 and 12
+        // White: moves of 67.24 and 21.09 give a weighted mean of 33.78 and a harmonic mean of 32.11
+        // Black: moves of 44.53 and 32.40 give a weighted mean of 37.63 and a harmonic mean of 37.51
+        let white_cps = [Some(0), Some(-100), Some(100), Some(-300), Some(0)];
+        let moves = [reviewed("white", Some(100)), reviewed("black", Some(200)), reviewed("white", Some(400)), reviewed("black", Some(300))];
+        let accuracy = game_accuracy(&white_cps, &moves, Color::White);
+        assert_eq!(accuracy.white.accuracy, 32.9);
+        assert_eq!(accuracy.black.accuracy, 37.6);
+        assert_eq!(accuracy.white.acpl, 250.);
+        assert_eq!(accuracy.black.acpl, 250.);
+    }
+
+    #[test]
+    fn reviews_single_move_game() {
+        let accuracy = game_accuracy(&[Some(20), Some(-200)], &[reviewed("white", Some(220))], Color::White);
+        assert_eq!(accuracy.white.accuracy, 42.0);
+        assert_eq!(accuracy.white.acpl, 220.);
+        // A side without moves played perfectly
+        assert_eq!(accuracy.black.accuracy, 100.);
+        assert_eq!(accuracy.black.acpl, 0.);
+    }
+
+    #[test]
+    fn leaves_out_moves_next_to_missing_evaluations() {
+        let moves = [reviewed("black", None), reviewed("white", None)];
+        let accuracy = game_accuracy(&[Some(0), None, Some(-500)], &moves, Color::Black);
+        assert_eq!(accuracy.white.accuracy, 100.);
+        assert_eq!(accuracy.black.accuracy, 100.);
+        assert_eq!(accuracy.black.acpl, 0.);
+    }
+}
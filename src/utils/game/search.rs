@@ -0,0 +1,380 @@
+// Importing necessary modules and structures from the `std`, `rocket` and `shakmaty` crates.
+use std::time::{Duration, Instant};
+use rocket::async_trait;
+use shakmaty::{Chess, Color, MoveList, Position, Role};
+use shakmaty::variant::VariantPosition;
+
+// Importing the internal result type and the `MoveGenerator` trait.
+use crate::utils::errors::internal::InternalResult;
+use crate::utils::game::generator::{MoveGenerator, PvLine, Score, SearchLimits, SearchResult};
+
+// Score of a checkmate, mate scores are reduced by the distance to the root, so that shorter mates are preferred.
+const MATE: i32 = 30_000;
+// Every score above this threshold is a mate score.
+const MATE_THRESHOLD: i32 = MATE - 1_000;
+// Maximum distance to the root, after which the search only evaluates the position.
+const MAX_PLY: i32 = 64;
+// Time budget of a single search without a time limit, the move of the deepest iteration is played once it is exhausted.
+const TIME_BUDGET: Duration = Duration::from_secs(3);
+// Amount of nodes between two checks of the time budget.
+const CHECK_INTERVAL: u64 = 2048;
+// Non-pawn material of both sides, below which the endgame tables are used.
+const ENDGAME_MATERIAL: i32 = 1_300;
+
+// Material values in centipawns, indexed by `role_index`.
+const MATERIAL: [i32; 6] = [100, 320, 330, 500, 900, 0];
+
+// Piece-square tables from white's point of view, written from the eighth rank down to the first.
+const PAWN_TABLE: [i32; 64] = [
+    0, 0, 0, 0, 0, 0, 0, 0,
+    50, 50, 50, 50, 50, 50, 50, 50,
+    10, 10, 20, 30, 30, 20, 10, 10,
+    5, 5, 10, 25, 25, 10, 5, 5,
+    0, 0, 0, 20, 20, 0, 0, 0,
+    5, -5, -10, 0, 0, -10, -5, 5,
+    5, 10, 10, -20, -20, 10, 10, 5,
+    0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+const KNIGHT_TABLE: [i32; 64] = [
+    -50, -40, -30, -30, -30, -30, -40, -50,
+    -40, -20, 0, 0, 0, 0, -20, -40,
+    -30, 0, 10, 15, 15, 10, 0, -30,
+    -30, 5, 15, 20, 20, 15, 5, -30,
+    -30, 0, 15, 20, 20, 15, 0, -30,
+    -30, 5, 10, 15, 15, 10, 5, -30,
+    -40, -20, 0, 5, 5, 0, -20, -40,
+    -50, -40, -30, -30, -30, -30, -40, -50,
+];
+
+const BISHOP_TABLE: [i32; 64] = [
+    -20, -10, -10, -10, -10, -10, -10, -20,
+    -10, 0, 0, 0, 0, 0, 0, -10,
+    -10, 0, 5, 10, 10, 5, 0, -10,
+    -10, 5, 5, 10, 10, 5, 5, -10,
+    -10, 0, 10, 10, 10, 10, 0, -10,
+    -10, 10, 10, 10, 10, 10, 10, -10,
+    -10, 5, 0, 0, 0, 0, 5, -10,
+    -20, -10, -10, -10, -10, -10, -10, -20,
+];
+
+const ROOK_TABLE: [i32; 64] = [
+    0, 0, 0, 0, 0, 0, 0, 0,
+    5, 10, 10, 10, 10, 10, 10, 5,
+    -5, 0, 0, 0, 0, 0, 0, -5,
+    -5, 0, 0, 0, 0, 0, 0, -5,
+    -5, 0, 0, 0, 0, 0, 0, -5,
+    -5, 0, 0, 0, 0, 0, 0, -5,
+    -5, 0, 0, 0, 0, 0, 0, -5,
+    0, 0, 0, 5, 5, 0, 0, 0,
+];
+
+const QUEEN_TABLE: [i32; 64] = [
+    -20, -10, -10, -5, -5, -10, -10, -20,
+    -10, 0, 0, 0, 0, 0, 0, -10,
+    -10, 0, 5, 5, 5, 5, 0, -10,
+    -5, 0, 5, 5, 5, 5, 0, -5,
+    0, 0, 5, 5, 5, 5, 0, -5,
+    -10, 5, 5, 5, 5, 5, 0, -10,
+    -10, 0, 5, 0, 0, 0, 0, -10,
+    -20, -10, -10, -5, -5, -10, -10, -20,
+];
+
+const KING_MIDDLEGAME_TABLE: [i32; 64] = [
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -20, -30, -30, -40, -40, -30, -30, -20,
+    -10, -20, -20, -20, -20, -20, -20, -10,
+    20, 20, 0, 0, 0, 0, 20, 20,
+    20, 30, 10, 0, 0, 10, 30, 20,
+];
+
+const KING_ENDGAME_TABLE: [i32; 64] = [
+    -50, -40, -30, -20, -20, -30, -40, -50,
+    -30, -20, -10, 0, 0, -10, -20, -30,
+    -30, -10, 20, 30, 30, 20, -10, -30,
+    -30, -10, 30, 40, 40, 30, -10, -30,
+    -30, -10, 30, 40, 40, 30, -10, -30,
+    -30, -10, 20, 30, 30, 20, -10, -30,
+    -30, -30, 0, 0, 0, 0, -30, -30,
+    -50, -30, -30, -30, -30, -30, -30, -50,
+];
+
+// The `Searcher` structure is a pure Rust alpha-beta engine, which is used if no stockfish binary is available.
+pub struct Searcher;
+
+#[async_trait]
+impl MoveGenerator for Searcher {
+    async fn search(&mut self, board: &VariantPosition, limits: &SearchLimits) -> InternalResult<SearchResult> {
+        // The evaluation only knows the rules of standard chess
+        let VariantPosition::Chess(board) = board.clone() else {
+            return Err("SEARCH: The built-in search only plays standard chess");
+        };
+        let depth = limits.depth.unwrap_or(MAX_PLY as u32);
+        let budget = limits.time_budget(board.turn()).unwrap_or(TIME_BUDGET);
+        let max_nodes = limits.nodes;
+        // The search is cpu bound, so it must not block the async runtime
+        let task = tokio::task::spawn_blocking(move || Search::new(budget, max_nodes).run(&board, depth));
+        task.await.map_err(|_| "SEARCH: The search task was aborted")
+    }
+}
+
+// The `Search` structure holds the state of one iterative deepening search.
+struct Search {
+    deadline: Instant,
+    nodes: u64,
+    max_nodes: Option<u64>,
+    // Deepest ply reached by the search, including the quiescence search.
+    seldepth: u32,
+    aborted: bool,
+}
+
+impl Search {
+    // Method to create a new `Search`, which stops once the given time budget or node budget is exhausted.
+    fn new(budget: Duration, max_nodes: Option<u64>) -> Self {
+        Search {
+            deadline: Instant::now() + budget,
+            nodes: 0,
+            max_nodes,
+            seldepth: 0,
+            aborted: false,
+        }
+    }
+
+    // Method to search the position with increasing depth until `max_depth` is reached or the time is up.
+    fn run(&mut self, board: &Chess, max_depth: u32) -> SearchResult {
+        let started = Instant::now();
+        let mut moves = board.legal_moves();
+        order_moves(&mut moves);
+        let Some(mut best) = moves.first().cloned() else {
+            return SearchResult::default();
+        };
+        let mut best_score = 0;
+        let mut completed_depth = 0;
+
+        for depth in 1..=max_depth.max(1) {
+            let mut alpha = -MATE - 1;
+            let mut iteration_best = None;
+            for mov in moves.iter() {
+                let mut child = board.clone();
+                child.play_unchecked(mov);
+                let score = -self.negamax(&child, depth - 1, 1, -MATE - 1, -alpha);
+                if self.aborted {
+                    break;
+                }
+                if score > alpha {
+                    alpha = score;
+                    iteration_best = Some(mov.clone());
+                }
+            }
+            // The best move of the previous iteration is searched first, so a partial iteration can still be used
+            if let Some(mov) = iteration_best {
+                best = mov;
+                best_score = alpha;
+            }
+            if self.aborted {
+                break;
+            }
+            completed_depth = depth;
+            if alpha.abs() >= MATE_THRESHOLD {
+                break;
+            }
+            // Search the best move first in the next iteration
+            if let Some(index) = moves.iter().position(|mov| *mov == best) {
+                moves[..=index].rotate_right(1);
+            }
+        }
+
+        let elapsed = started.elapsed().as_secs_f64().max(0.001);
+        SearchResult {
+            best_move: Some(best.clone()),
+            ponder: None,
+            lines: vec![PvLine {
+                multipv: 1,
+                depth: completed_depth.max(1),
+                score: to_score(best_score),
+                wdl: None,
+                moves: vec![best],
+            }],
+            depth: completed_depth.max(1),
+            seldepth: self.seldepth.max(completed_depth.max(1)),
+            nodes: self.nodes,
+            nps: (self.nodes as f64 / elapsed) as u64,
+        }
+    }
+
+    // Method implementing the negamax alpha-beta search.
+    fn negamax(&mut self, board: &Chess, depth: u32, ply: i32, mut alpha: i32, beta: i32) -> i32 {
+        if self.tick(ply) {
+            return 0;
+        }
+        let mut moves = board.legal_moves();
+        if moves.is_empty() {
+            return if board.is_check() { -MATE + ply } else { 0 };
+        }
+        if board.is_insufficient_material() || board.halfmoves() >= 100 {
+            return 0;
+        }
+        if depth == 0 || ply >= MAX_PLY {
+            return self.quiescence(board, ply, alpha, beta);
+        }
+
+        order_moves(&mut moves);
+        for mov in moves.iter() {
+            let mut child = board.clone();
+            child.play_unchecked(mov);
+            let score = -self.negamax(&child, depth - 1, ply + 1, -beta, -alpha);
+            if self.aborted {
+                return 0;
+            }
+            if score >= beta {
+                return beta;
+            }
+            alpha = alpha.max(score);
+        }
+        alpha
+    }
+
+    // Method extending the search by captures until the position is quiet, which avoids the horizon effect.
+    fn quiescence(&mut self, board: &Chess, ply: i32, mut alpha: i32, beta: i32) -> i32 {
+        if self.tick(ply) {
+            return 0;
+        }
+        if ply >= MAX_PLY {
+            return evaluate(board);
+        }
+
+        let mut moves = if board.is_check() {
+            // There is no quiet alternative while in check, so all evasions have to be searched
+            let evasions = board.legal_moves();
+            if evasions.is_empty() {
+                return -MATE + ply;
+            }
+            evasions
+        } else {
+            let stand_pat = evaluate(board);
+            if stand_pat >= beta {
+                return beta;
+            }
+            alpha = alpha.max(stand_pat);
+            board.capture_moves()
+        };
+
+        order_moves(&mut moves);
+        for mov in moves.iter() {
+            let mut child = board.clone();
+            child.play_unchecked(mov);
+            let score = -self.quiescence(&child, ply + 1, -beta, -alpha);
+            if self.aborted {
+                return 0;
+            }
+            if score >= beta {
+                return beta;
+            }
+            alpha = alpha.max(score);
+        }
+        alpha
+    }
+
+    // Method counting a node at the given ply and checking the budgets, returns `true` if the search has to stop.
+    fn tick(&mut self, ply: i32) -> bool {
+        self.nodes += 1;
+        self.seldepth = self.seldepth.max(ply as u32);
+        if self.max_nodes.is_some_and(|max_nodes| self.nodes >= max_nodes) {
+            self.aborted = true;
+        }
+        if self.nodes.is_multiple_of(CHECK_INTERVAL) && Instant::now() >= self.deadline {
+            self.aborted = true;
+        }
+        self.aborted
+    }
+}
+
+// Function to convert an internal score into a `Score`, mate scores are converted into moves until mate.
+fn to_score(score: i32) -> Score {
+    if score >= MATE_THRESHOLD {
+        Score::Mate((MATE - score + 1) / 2)
+    } else if score <= -MATE_THRESHOLD {
+        Score::Mate(-(MATE + score + 1) / 2)
+    } else {
+        Score::Cp(score)
+    }
+}
+
+// Function to sort the moves, so that promotions and captures of valuable pieces by cheap pieces come first.
+fn order_moves(moves: &mut MoveList) {
+    moves.sort_by_key(|mov| {
+        let capture = mov.capture().map_or(0, |victim| 10 * MATERIAL[role_index(victim)] - MATERIAL[role_index(mov.role())]);
+        let promotion = mov.promotion().map_or(0, |role| MATERIAL[role_index(role)]);
+        -(capture + promotion)
+    });
+}
+
+// Function to evaluate a position by material and piece-square tables from the view of the side to move.
+fn evaluate(board: &Chess) -> i32 {
+    let endgame = non_pawn_material(board) <= ENDGAME_MATERIAL;
+    let mut score = 0;
+    for (square, piece) in board.board().clone() {
+        // The tables are written from white's view, so black pieces use the mirrored square
+        let index = match piece.color {
+            Color::White => usize::from(square.flip_vertical()),
+            Color::Black => usize::from(square),
+        };
+        let value = MATERIAL[role_index(piece.role)] + piece_square_table(piece.role, endgame)[index];
+        score += piece.color.fold_wb(value, -value);
+    }
+    board.turn().fold_wb(score, -score)
+}
+
+// Function to sum up the material of all knights, bishops, rooks and queens on the board.
+fn non_pawn_material(board: &Chess) -> i32 {
+    board.board().clone().into_iter()
+        .filter(|(_, piece)| piece.role != Role::Pawn)
+        .map(|(_, piece)| MATERIAL[role_index(piece.role)])
+        .sum()
+}
+
+// Function to select the piece-square table of a role.
+fn piece_square_table(role: Role, endgame: bool) -> &'static [i32; 64] {
+    match role {
+        Role::Pawn => &PAWN_TABLE,
+        Role::Knight => &KNIGHT_TABLE,
+        Role::Bishop => &BISHOP_TABLE,
+        Role::Rook => &ROOK_TABLE,
+        Role::Queen => &QUEEN_TABLE,
+        Role::King if endgame => &KING_ENDGAME_TABLE,
+        Role::King => &KING_MIDDLEGAME_TABLE,
+    }
+}
+
+// Function to map a role to its index in the `MATERIAL` table.
+fn role_index(role: Role) -> usize {
+    match role {
+        Role::Pawn => 0,
+        Role::Knight => 1,
+        Role::Bishop => 2,
+        Role::Rook => 3,
+        Role::Queen => 4,
+        Role::King => 5,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shakmaty::fen::Fen;
+    use shakmaty::CastlingMode;
+
+    #[test]
+    fn reports_the_deepest_ply_of_the_quiescence_search() {
+        // The queens and rooks can be traded on d5, so the captures go on behind the nominal depth
+        let fen: Fen = "3r2k1/3q1ppp/8/3p4/8/8/3Q1PPP/3R2K1 w - - 0 1".parse().unwrap();
+        let board: Chess = fen.into_position(CastlingMode::Standard).unwrap();
+        let result = Search::new(TIME_BUDGET, None).run(&board, 1);
+        assert_eq!(result.depth, 1);
+        assert!(result.seldepth > 1, "{}", result.seldepth);
+        assert!(result.seldepth < MAX_PLY as u32, "{}", result.seldepth);
+    }
+}
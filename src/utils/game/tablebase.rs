@@ -0,0 +1,205 @@
+// Importing necessary modules and structures from the `std`, `rocket`, `serde`, `shakmaty` and `shakmaty_syzygy` crates.
+use std::path::Path;
+use std::sync::Arc;
+use rocket::async_trait;
+use serde::Serialize;
+use shakmaty::{CastlingMode, Chess, EnPassantMode, Position};
+use shakmaty::variant::VariantPosition;
+use shakmaty::fen::Fen;
+use shakmaty::san::SanPlus;
+use shakmaty_syzygy::{AmbiguousWdl, MaybeRounded, SyzygyError, Tablebase, Wdl};
+
+// Importing the internal result type and the `MoveGenerator` trait.
+use crate::utils::errors::internal::InternalResult;
+use crate::utils::game::generator::{MoveGenerator, SearchLimits, SearchResult};
+
+// The `EndgameTablebase` structure holds the Syzygy tables of a local directory.
+pub struct EndgameTablebase {
+    tables: Tablebase<Chess>,
+}
+
+// Enum representing the WDL value of a position under the 50-move rule from the view of the side to move.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TablebaseWdl {
+    Loss,
+    // The position might be a blessed loss, because the DTZ value is rounded.
+    MaybeLoss,
+    BlessedLoss,
+    Draw,
+    CursedWin,
+    // The position might be a cursed win, because the DTZ value is rounded.
+    MaybeWin,
+    Win,
+}
+
+// Structure representing the tablebase verdict of a position, which is returned as JSON.
+#[derive(Serialize)]
+pub struct TablebaseVerdict {
+    pub fen: String,
+    pub wdl: TablebaseWdl,
+    // Distance to the next capture or pawn move, `None` if the position is already over.
+    pub dtz: Option<i32>,
+    // `false` if the DTZ value might be off by one.
+    pub precise_dtz: bool,
+    // Every legal move ordered from the best to the worst, the values are from the view of the side to move.
+    pub moves: Vec<TablebaseMove>,
+}
+
+// Structure representing a legal move together with the tablebase verdict of the position after it.
+#[derive(Serialize)]
+pub struct TablebaseMove {
+    pub uci: String,
+    pub san: String,
+    pub wdl: TablebaseWdl,
+    pub dtz: Option<i32>,
+}
+
+impl EndgameTablebase {
+    // Method to open every Syzygy table of the given directory, fails if there is no table.
+    pub fn open(path: &Path) -> InternalResult<Self> {
+        let mut tables = Tablebase::new();
+        let count = tables.add_directory(path).map_err(|_| "TABLEBASE: Could not read the tablebase directory")?;
+        if count == 0 {
+            return Err("TABLEBASE: The tablebase directory contains no Syzygy tables");
+        }
+        info!("TABLEBASE: Opened {} tables for up to {} pieces", count, tables.max_pieces());
+        Ok(EndgameTablebase { tables })
+    }
+
+    // Method to check if the position is small enough to be in the tablebase, without touching any file.
+    pub fn covers(&self, board: &Chess) -> bool {
+        board.board().occupied().count() <= self.tables.max_pieces() && !board.castles().any()
+    }
+
+    // Method to get the move which keeps the best outcome under the 50-move rule.
+    pub fn best_move(&self, board: &Chess) -> InternalResult<Option<shakmaty::Move>> {
+        let best = self.tables.best_move(board).map_err(probe_error)?;
+        Ok(best.map(|(mov, _)| mov))
+    }
+
+    // Method to probe a position and every position after one of its legal moves.
+    pub fn probe(&self, board: &Chess) -> InternalResult<TablebaseVerdict> {
+        let (wdl, dtz) = match board.outcome() {
+            Some(outcome) => (TablebaseWdl::from(Wdl::from_outcome(outcome, board.turn())), None),
+            None => {
+                let wdl = self.tables.probe_wdl(board).map_err(probe_error)?;
+                let dtz = self.tables.probe_dtz(board).map_err(probe_error)?;
+                (TablebaseWdl::from(wdl), Some(dtz))
+            }
+        };
+
+        let mut moves = Vec::new();
+        for mov in board.legal_moves() {
+            let mut after = board.clone();
+            after.play_unchecked(&mov);
+            let (after_wdl, after_dtz) = match after.outcome() {
+                Some(outcome) => (Wdl::from_outcome(outcome, after.turn()).signum(), 0),
+                None => {
+                    let after_wdl = self.tables.probe_wdl(&after).map_err(probe_error)?;
+                    let after_dtz = self.tables.probe_dtz(&after).map_err(probe_error)?;
+                    (after_wdl.signum(), after_dtz.ignore_rounding().0)
+                }
+            };
+            moves.push((after_wdl, after_dtz, TablebaseMove {
+                uci: mov.to_uci(CastlingMode::Standard).to_string(),
+                san: SanPlus::from_move(board.clone(), &mov).to_string(),
+                wdl: TablebaseWdl::from_signum(-after_wdl),
+                dtz: after.outcome().is_none().then_some(-after_dtz),
+            }));
+        }
+        // The worse the position is for the opponent the better the move, shorter wins and longer losses come first
+        moves.sort_by_key(|(after_wdl, after_dtz, _)| (*after_wdl, -*after_dtz));
+
+        Ok(TablebaseVerdict {
+            fen: Fen::from_position(board.clone(), EnPassantMode::Legal).to_string(),
+            wdl,
+            dtz: dtz.map(|dtz| dtz.ignore_rounding().0),
+            precise_dtz: dtz.is_none_or(|dtz| matches!(dtz, MaybeRounded::Precise(_))),
+            moves: moves.into_iter().map(|(_, _, mov)| mov).collect(),
+        })
+    }
+}
+
+// Function to convert a probe error into an internal error.
+fn probe_error(err: SyzygyError) -> &'static str {
+    match err {
+        SyzygyError::Castling => "TABLEBASE: Positions with castling rights are not in the tablebase",
+        SyzygyError::TooManyPieces => "TABLEBASE: The position has too many pieces",
+        SyzygyError::MissingTable { .. } => "TABLEBASE: The table of this material is missing",
+        SyzygyError::ProbeFailed { .. } => "TABLEBASE: Could not probe the table",
+    }
+}
+
+impl TablebaseWdl {
+    // Method to convert a WDL signum, which is all that is known of the position after a move.
+    fn from_signum(signum: i32) -> Self {
+        match signum {
+            2 => TablebaseWdl::Win,
+            1 => TablebaseWdl::CursedWin,
+            0 => TablebaseWdl::Draw,
+            -1 => TablebaseWdl::BlessedLoss,
+            _ => TablebaseWdl::Loss,
+        }
+    }
+}
+
+impl From<AmbiguousWdl> for TablebaseWdl {
+    fn from(wdl: AmbiguousWdl) -> Self {
+        match wdl {
+            AmbiguousWdl::Loss => TablebaseWdl::Loss,
+            AmbiguousWdl::MaybeLoss => TablebaseWdl::MaybeLoss,
+            AmbiguousWdl::BlessedLoss => TablebaseWdl::BlessedLoss,
+            AmbiguousWdl::Draw => TablebaseWdl::Draw,
+            AmbiguousWdl::CursedWin => TablebaseWdl::CursedWin,
+            AmbiguousWdl::MaybeWin => TablebaseWdl::MaybeWin,
+            AmbiguousWdl::Win => TablebaseWdl::Win,
+        }
+    }
+}
+
+impl From<Wdl> for TablebaseWdl {
+    fn from(wdl: Wdl) -> Self {
+        TablebaseWdl::from_signum(wdl.signum())
+    }
+}
+
+// The `TablebaseMover` structure plays perfect tablebase moves and asks the wrapped move generator for larger positions.
+pub struct TablebaseMover {
+    tablebase: Arc<EndgameTablebase>,
+    inner: Box<dyn MoveGenerator>,
+}
+
+impl TablebaseMover {
+    // Method to create a new `TablebaseMover` around the given move generator.
+    pub fn new(tablebase: Arc<EndgameTablebase>, inner: Box<dyn MoveGenerator>) -> Self {
+        TablebaseMover { tablebase, inner }
+    }
+}
+
+#[async_trait]
+impl MoveGenerator for TablebaseMover {
+    async fn search(&mut self, board: &VariantPosition, limits: &SearchLimits) -> InternalResult<SearchResult> {
+        // The tables only contain endgames of standard chess
+        let covered = match board {
+            VariantPosition::Chess(chess) if self.tablebase.covers(chess) => Some(chess.clone()),
+            _ => None
+        };
+        if let Some(position) = covered {
+            // Probing reads the table files, so it must not block the async runtime
+            let tablebase = self.tablebase.clone();
+            let best = tokio::task::spawn_blocking(move || tablebase.best_move(&position)).await;
+            match best {
+                Ok(Ok(Some(mov))) => return Ok(SearchResult::from_move(mov)),
+                Ok(Err(err)) => warn!("{}, searching instead", err),
+                _ => {}
+            }
+        }
+        self.inner.search(board, limits).await
+    }
+}
+
+// Asynchronous function to probe a position without blocking the async runtime.
+pub async fn probe(tablebase: Arc<EndgameTablebase>, board: Chess) -> InternalResult<TablebaseVerdict> {
+    tokio::task::spawn_blocking(move || tablebase.probe(&board)).await.map_err(|_| "TABLEBASE: The probe task was aborted")?
+}
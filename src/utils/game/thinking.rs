@@ -0,0 +1,90 @@
+// Importing necessary modules and structures from the `serde`, `shakmaty` and `tokio` crates.
+use serde::Serialize;
+use shakmaty::{EnPassantMode, Move, Position};
+use shakmaty::variant::VariantPosition;
+use shakmaty::fen::Fen;
+use shakmaty::san::SanPlus;
+use tokio::sync::broadcast::{channel, Receiver, Sender};
+
+// Importing the search result types.
+use crate::utils::game::analysis::WdlProbabilities;
+use crate::utils::game::generator::{PvLine, Score, Wdl};
+
+// Amount of events a slow spectator may fall behind, before it skips the oldest ones.
+const CAPACITY: usize = 64;
+
+// Enum representing what the engine of a game is doing, which is streamed to the game page.
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ThinkingEvent {
+    // The engine started to search its reply.
+    Searching,
+    // The engine reported a new principal variation, the score and the wdl are from the view of white.
+    Info {
+        depth: u32,
+        score: Score,
+        wdl: Option<WdlProbabilities>,
+        nodes: u64,
+        nps: u64,
+        // The principal variation in SAN notation.
+        pv: Vec<String>,
+    },
+    // The engine played its reply.
+    Move {
+        uci: String,
+        san: String,
+        fen: String,
+    },
+}
+
+impl ThinkingEvent {
+    // Method to create an `Info` event out of the best line of a running search on the given board.
+    pub fn info(board: &VariantPosition, line: &PvLine, nodes: u64, nps: u64) -> Self {
+        let mut position = board.clone();
+        let pv = line.moves.iter().map(|mov| SanPlus::from_move_and_play_unchecked(&mut position, mov).to_string()).collect();
+        ThinkingEvent::Info {
+            depth: line.depth,
+            score: line.score.white_view(board.turn()),
+            wdl: line.wdl.map(|wdl| WdlProbabilities::from(board.turn().fold_wb(wdl, Wdl { win: wdl.loss, draw: wdl.draw, loss: wdl.win }))),
+            nodes,
+            nps,
+            pv,
+        }
+    }
+
+    // Method to create a `Move` event out of the reply of the engine, which has not been played on the board yet.
+    pub fn reply(board: &VariantPosition, mov: &Move) -> Self {
+        let mut position = board.clone();
+        let san = SanPlus::from_move_and_play_unchecked(&mut position, mov).to_string();
+        ThinkingEvent::Move {
+            uci: mov.to_uci(board.castles().mode()).to_string(),
+            san,
+            fen: Fen::from_position(position, EnPassantMode::Legal).to_string(),
+        }
+    }
+}
+
+// The `ThinkingStream` structure broadcasts the thinking of the engine of a game to everyone who follows it.
+#[derive(Clone, Debug)]
+pub struct ThinkingStream {
+    sender: Sender<ThinkingEvent>,
+}
+
+impl ThinkingStream {
+    // Method to create a new `ThinkingStream` without any followers.
+    pub fn new() -> Self {
+        let (sender, _) = channel(CAPACITY);
+        ThinkingStream { sender }
+    }
+
+    // Method to follow the stream, only events sent afterwards are received.
+    pub fn subscribe(&self) -> Receiver<ThinkingEvent> {
+        self.sender.subscribe()
+    }
+
+    // Method to send an event to every follower.
+    pub fn send(&self, event: ThinkingEvent) {
+        // Nobody might be following the game, which is no error
+        let _ = self.sender.send(event);
+    }
+}
@@ -0,0 +1,98 @@
+// Importing necessary modules and structures from the `rand`, `rocket` and `shakmaty` crates.
+use rand::Rng;
+use rand::distributions::WeightedIndex;
+use rand::prelude::Distribution;
+use rand::seq::SliceRandom;
+use rocket::async_trait;
+use shakmaty::{Move, Position};
+use shakmaty::variant::VariantPosition;
+
+// Importing the internal result type and the `MoveGenerator` trait.
+use crate::utils::errors::internal::InternalResult;
+use crate::utils::game::generator::{MoveGenerator, SearchLimits, SearchResult};
+
+// The weakest elo a game can be played at.
+pub const WEAKEST_ELO: u16 = 100;
+// Amount of candidate moves the engine reports for the weakening layer.
+pub const CANDIDATES: u32 = 5;
+
+// Temperature of the candidate sampling in centipawns, just below the engine and at the weakest elo.
+const MIN_TEMPERATURE: f64 = 10.;
+const MAX_TEMPERATURE: f64 = 300.;
+// Probability of a random legal move at the weakest elo.
+const RANDOM_MOVE_RATE: f64 = 0.25;
+// Probability of overlooking that a piece of the opponent can be taken for free at the weakest elo.
+const MISSED_CAPTURE_RATE: f64 = 0.6;
+
+// The `WeakenedMover` structure plays below the weakest elo of the engine, by picking worse candidate moves like a beginner.
+// The wrapped move generator has to search at the weakest elo of the engine with `CANDIDATES` lines.
+pub struct WeakenedMover {
+    inner: Box<dyn MoveGenerator>,
+    // How far the elo is below the engine, from 0 just below the weakest elo of the engine to 1 at `WEAKEST_ELO`.
+    weakness: f64,
+}
+
+impl WeakenedMover {
+    // Method to create a new `WeakenedMover` around the given move generator, `engine_elo` is the weakest elo of the engine.
+    pub fn new(inner: Box<dyn MoveGenerator>, elo: u16, engine_elo: u16) -> Self {
+        let span = f64::from(engine_elo.saturating_sub(WEAKEST_ELO).max(1));
+        let weakness = (f64::from(engine_elo.saturating_sub(elo)) / span).clamp(0., 1.);
+        WeakenedMover { inner, weakness }
+    }
+
+    // Method to pick a candidate, a candidate is the more likely the closer its score is to the best one.
+    fn sample(&self, candidates: &[(Move, i32)]) -> Option<Move> {
+        let best = candidates.iter().map(|(_, cp)| *cp).max()?;
+        let temperature = MIN_TEMPERATURE + (MAX_TEMPERATURE - MIN_TEMPERATURE) * self.weakness;
+        let weights = candidates.iter().map(|(_, cp)| (f64::from(cp - best) / temperature).exp());
+        let distribution = WeightedIndex::new(weights).ok()?;
+        Some(candidates[distribution.sample(&mut rand::thread_rng())].0.clone())
+    }
+}
+
+#[async_trait]
+impl MoveGenerator for WeakenedMover {
+    async fn search(&mut self, board: &VariantPosition, limits: &SearchLimits) -> InternalResult<SearchResult> {
+        let mut result = self.inner.search(board, limits).await?;
+        let mut rng = rand::thread_rng();
+
+        // Beginners sometimes play a move without any idea behind it
+        let mov = if rng.gen_bool(RANDOM_MOVE_RATE * self.weakness) {
+            board.legal_moves().choose(&mut rng).cloned()
+        } else {
+            let mut candidates: Vec<(Move, i32)> = result.lines.iter()
+                .filter_map(|line| Some((line.moves.first()?.clone(), line.score.centipawns())))
+                .collect();
+            // Beginners often do not see that a piece can be taken for free
+            if rng.gen_bool(MISSED_CAPTURE_RATE * self.weakness) {
+                let quiet: Vec<(Move, i32)> = candidates.iter().filter(|(mov, _)| !captures_hanging_piece(board, mov)).cloned().collect();
+                if !quiet.is_empty() {
+                    candidates = quiet;
+                }
+            }
+            self.sample(&candidates)
+        };
+
+        if let Some(mov) = mov {
+            // The expected reply belongs to the best move only
+            if result.best_move.as_ref() != Some(&mov) {
+                result.ponder = None;
+            }
+            result.best_move = Some(mov);
+        }
+        Ok(result)
+    }
+}
+
+// Function to check if a move takes a piece that is not defended.
+fn captures_hanging_piece(board: &VariantPosition, mov: &Move) -> bool {
+    let Some(from) = mov.from() else {
+        return false;
+    };
+    if mov.capture().is_none() {
+        return false;
+    }
+    // The moving piece leaves its square, so defenders behind it are taken into account
+    let occupied = board.board().occupied().without(from);
+    board.board().attacks_to(mov.to(), !board.turn(), occupied).is_empty()
+}
@@ -1,5 +1,7 @@
 pub mod game;
 pub mod session;
+pub mod exhibition;
 pub mod db;
 pub mod requests;
 pub mod errors;
+pub mod responses;
@@ -3,6 +3,33 @@ pub struct GameSettings {
     pub new_session: Option<bool>,
     pub username: String,
     pub difficulty: i16,
+    // Name of a configured bot, which is played instead of the difficulty.
+    pub bot: Option<String>,
+    // Elo the engine plays at instead of the elo of the difficulty, it has to be in the range of the engine.
+    pub elo: Option<u16>,
     pub color: char,
+    // Initial time of both sides in minutes, the game is played without a clock if it is missing or zero.
+    pub clock_minutes: Option<u16>,
+    // Increment per move in seconds.
+    pub clock_increment: Option<u16>,
+    // `knight`, `rook`, `queen` or `move`, the game is played without odds if it is missing or empty.
+    pub odds: Option<String>,
+    // The side that gives the odds, `u` for the user and `e` for the engine.
+    pub odds_giver: Option<char>,
+    // Chess960 games can not be played with odds.
+    pub chess960: Option<bool>,
+    // Index of the Chess960 start position from 0 to 959, a random position is played if it is missing.
+    pub chess960_index: Option<u16>,
+    // UCI name of the variant, e.g. `atomic` or `crazyhouse`, standard chess is played if it is missing or empty.
+    pub variant: Option<String>,
+    // FEN of a custom start position with the user to move, the game is unranked.
+    pub fen: Option<String>,
 }
 
+
+#[derive(FromForm)]
+pub struct ExhibitionSettings {
+    // The difficulties or the names of configured bots, adaptive bots can not play exhibitions.
+    pub white: String,
+    pub black: String,
+}
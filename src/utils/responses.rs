@@ -0,0 +1,93 @@
+use rocket::serde::json::{Json, Value};
+use serde::Serialize;
+use shakmaty::{Color, EnPassantMode};
+use shakmaty::fen::Fen;
+use shakmaty::variant::VariantPosition;
+use crate::utils::game::{GameAccuracy, Score, WdlProbabilities};
+
+// Structure representing the suggested move of `/hint`.
+#[derive(Serialize)]
+pub struct Hint {
+    pub uci: String,
+    pub san: String,
+    // Evaluation of the hinted move from the view of the user, `None` if the engine did not send a line.
+    pub score: Option<Score>,
+    pub wdl: Option<WdlProbabilities>,
+    // The amount of hints used in this game, including this one.
+    pub hints_used: u32,
+}
+
+// Enum representing the reply of `/move`, which is the fen after the engine's reply unless a flag fell.
+#[derive(Responder)]
+pub enum MoveReply {
+    #[response(content_type = "plain")]
+    Fen(String),
+    #[response(content_type = "json")]
+    Flagged(Json<FlagFall>),
+}
+
+impl MoveReply {
+    // Method to reply the side that ran out of time and the position its flag fell in.
+    pub fn flagged(color: Color, board: &VariantPosition) -> Self {
+        MoveReply::Flagged(Json(FlagFall {
+            flagged: color.fold_wb("white", "black"),
+            fen: Fen::from_position(board.clone(), EnPassantMode::Legal).to_string(),
+        }))
+    }
+}
+
+// Structure representing a fallen flag, the game is over and is resolved by `/game_end`.
+#[derive(Serialize)]
+pub struct FlagFall {
+    // `white` or `black`, the side that lost on time.
+    pub flagged: &'static str,
+    pub fen: String,
+}
+
+// Structure representing the result of a finished game, which is returned by `/game_end`.
+#[derive(Serialize)]
+pub struct GameEnd {
+    // `white` or `black`, `None` if the game is drawn.
+    pub winner: Option<&'static str>,
+    // The score of the scoreboard entry, `None` if the user did not win or the game is unranked.
+    pub score: Option<f32>,
+    pub hints_used: u32,
+    // The adjusted rating of the user, `None` if the game was not adaptive.
+    pub rating: Option<u16>,
+    // The id of the post-game review, which is available at `/review/<id>` once the game is reviewed in the background.
+    pub review_id: Option<i64>,
+    // The accuracy and the average centipawn loss of both sides.
+    // `None` if the review is still pending, it follows at `/review/<id>` then, or if the review failed.
+    pub accuracy: Option<GameAccuracy>,
+    // `false` if the game started from a custom position or the engine could not limit its strength, these games are not scored.
+    pub ranked: bool,
+}
+
+// Structure representing a stored post-game review, which is returned by `/review/<id>`.
+#[derive(Serialize)]
+pub struct ReviewReport {
+    pub id: i64,
+    pub username: String,
+    // `pending`, `done` or `failed`.
+    pub status: String,
+    // The accuracy and the average centipawn loss of both sides, `None` until the review is done.
+    pub accuracy: Option<GameAccuracy>,
+    // The reviewed moves, which are empty until the review is done.
+    pub moves: Value,
+}
+
+// Structure representing the elo range games can be played at, which is returned by `/strength`.
+#[derive(Serialize)]
+pub struct StrengthRange {
+    pub min: u16,
+    pub max: u16,
+}
+
+// Structure representing a configured bot, which is returned by `/bots`.
+#[derive(Serialize)]
+pub struct BotEntry {
+    pub name: String,
+    // Name of the engine of the registry the bot plays with.
+    pub engine: String,
+    pub elo: u16,
+}
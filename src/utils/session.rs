@@ -13,7 +13,7 @@ use cookies::{set_session_key, get_session_key, remove_session_key};
 use crate::utils::game::Game;
 
 // Constant representing the session key reference.
-const SESSION_KEY_REF: &'static str = "session_key";
+const SESSION_KEY_REF: &str = "session_key";
 
 // Type alias for `Game`.
 type T = Game;
@@ -22,7 +22,9 @@ type T = Game;
 mod structs {
     // Importing necessary modules and structures from the `std`, `tokio`, `uuid`, and `crate` crates.
     use tokio::sync::{Mutex, MutexGuard, RwLock};
+    use tokio::sync::broadcast::Receiver;
     use std::collections::HashMap;
+    use crate::utils::game::{ThinkingEvent, ThinkingStream};
     use crate::utils::session::T;
     use std::sync::Arc;
     use uuid::Uuid;
@@ -64,18 +66,26 @@ mod structs {
     pub struct Session {
         // A thread-safe, mutable game state.
         state: Arc<Mutex<T>>,
+        // The thinking stream of the game, which can be followed while the game state is locked by a search.
+        thinking: ThinkingStream,
     }
 
     impl Session {
         // Method to create a new `Session` with a given game state.
         pub fn new(o: T) -> Self {
             Session {
+                thinking: o.thinking.clone(),
                 state: Arc::new(Mutex::new(o))
             }
         }
 
+        // Method to follow the thinking of the engine of the game.
+        pub fn thinking(&self) -> Receiver<ThinkingEvent> {
+            self.thinking.subscribe()
+        }
+
         // Asynchronous method to get the game state of the session.
-        pub async fn get(&self) -> MutexGuard<T> {
+        pub async fn get(&self) -> MutexGuard<'_, T> {
             self.state.lock().await
         }
     }
@@ -109,7 +119,7 @@ mod cookies {
 pub async fn add_session(state: T, cookie_jar: &CookieJar<'_>, session_handler: &State<SessionHandler>) {
     let id = Uuid::new_v4();
     let session = Session::new(state);
-    session_handler.add(id.clone(), session).await;
+    session_handler.add(id, session).await;
     set_session_key(cookie_jar, id);
 }
 
@@ -5,8 +5,24 @@ const playerId = document.getElementById("player");
 const opponentId = document.getElementById("opponent");
 const scoreboardDescription = document.getElementById('scoreboard-description')
 const playerColorShort = document.getElementById("hidden-color").value;
+const hintButton = document.getElementById("hint-button");
 
+const chess960 = document.getElementById("hidden-chess960").value === "true";
+// chess.js only knows standard chess, so the moves of variants are checked by the server alone
+const variant = document.getElementById("hidden-variant").value;
+const serverRules = variant !== "chess";
+let variantFen = document.getElementById("hidden-fen").value;
+const pocketsText = document.getElementById("pockets");
+const dropForm = document.getElementById("drop-form");
+
+// chess.js does not know Chess960 castling, so the castling rights are left to the server in Chess960 games
+function loadFen(fen) {
+    return game.load(chess960 ? fen.replace(/^(\S+ \S+) \S+/, '$1 -') : fen);
+}
+
+// Odds and Chess960 games do not start from the standard position
 const game = new Chess();
+if (!serverRules) loadFen(document.getElementById("hidden-fen").value);
 
 const playerColor = playerColorShort === "w" ? "white" : "black";
 
@@ -17,46 +33,131 @@ const boardConfig = {
     snapbackSpeed: 'slow',
     snapSpeed: 'slow',
     orientation: playerColor,
-    position: game.fen(),
+    position: serverRules ? boardFen(variantFen) : game.fen(),
     onDragStart,
     onDrop,
 };
 const board = Chessboard2('board', boardConfig);
 
+// The board only shows the pieces, crazyhouse pockets and promotion markers are left out
+function boardFen(fen) {
+    return fen.split(' ')[0].replace(/\[.*\]/, '').replace(/~/g, '');
+}
+
+function turn() {
+    return serverRules ? variantFen.split(' ')[1] : game.turn();
+}
+
+function setVariantFen(fen) {
+    variantFen = fen;
+    board.position(boardFen(fen));
+    const pockets = fen.split(' ')[0].match(/\[(.*)\]/);
+    if (pockets) {
+        const white = pockets[1].replace(/[a-z]/g, '') || '-';
+        const black = pockets[1].replace(/[A-Z]/g, '') || '-';
+        pocketsText.innerText = `Pockets: White ${white}, Black ${black}`;
+    }
+}
+
 function isPiecePlayerColor(piece) {
     return new RegExp(`^${playerColorShort}`).test(piece);
 }
 
 function onDragStart(dragStartEvt) {
-    if (game.turn() !== playerColorShort || !isPiecePlayerColor(dragStartEvt.piece)) return false;
+    if (turn() !== playerColorShort || !isPiecePlayerColor(dragStartEvt.piece)) return false;
+    if (serverRules) return;
     game.moves({square: dragStartEvt.square, verbose: true}).forEach(move => board.addCircle(move.to));
 }
 
 function onDrop(dropEvt) {
+    if (serverRules) {
+        hintButton.innerText = 'Hint';
+        playVariantMove(dropEvt.source + dropEvt.target);
+        return;
+    }
     const move = game.move({
         from: dropEvt.source, to: dropEvt.target, promotion: 'q',
     });
     board.clearCircles();
-    if (!move) return 'snapback';
+    hintButton.innerText = 'Hint';
+    if (!move) {
+        // Chess960 castling is played by dropping the king on its rook
+        if (isChess960Castling(dropEvt.source, dropEvt.target)) castle(dropEvt.source, dropEvt.target);
+        return 'snapback';
+    }
     board.position(game.fen());
     opponentMove(dropEvt.source, dropEvt.target);
     checkGameOver();
 }
 
+function isChess960Castling(src, dest) {
+    const king = game.get(src);
+    const rook = game.get(dest);
+    return chess960 && king && rook && king.type === game.KING && rook.type === game.ROOK && king.color === playerColorShort && rook.color === playerColorShort;
+}
+
+// The server checks the castling move and replies with the position after the move of the engine
+async function castle(src, dest) {
+    try {
+        const fen = await fetchBoard(src, dest);
+        loadFen(fen);
+        board.position(game.fen());
+    } catch (e) {
+        console.error(e);
+    }
+    await checkGameOver();
+}
+
+// The server replies with the position after the move of the engine, an invalid move is taken back
+async function playVariantMove(uci) {
+    try {
+        const response = await fetch("/move", {
+            method: "POST", cache: "no-cache", headers: {
+                "Content-Type": "text/plain"
+            }, body: uci
+        });
+        const text = await readMoveReply(response);
+        if (response.ok || response.status === 406) setVariantFen(text); else setVariantFen(variantFen);
+    } catch (e) {
+        console.error(e);
+    }
+    await checkGameOver();
+}
+
+// Crazyhouse pieces are dropped from the pocket onto an empty square
+if (variant === "crazyhouse") {
+    dropForm.hidden = false;
+    dropForm.addEventListener('submit', (event) => {
+        event.preventDefault();
+        if (turn() !== playerColorShort) return;
+        const square = document.getElementById("drop-square").value.trim().toLowerCase();
+        playVariantMove(`${document.getElementById("drop-piece").value}@${square}`);
+    });
+}
+
 async function fetchBoard(src, dest) {
     const response = await fetch("/move", {
         method: "POST", cache: "no-cache", headers: {
             "Content-Type": "text/plain"
         }, body: src + dest
     });
-    return await response.text();
+    return await readMoveReply(response);
+}
+
+// The server replies with the fen after the engine's move, or with the side whose flag fell instead
+let flagged = null;
+async function readMoveReply(response) {
+    if (!(response.headers.get("Content-Type") ?? "").includes("json")) return await response.text();
+    const flagFall = await response.json();
+    flagged = flagFall.flagged;
+    return flagFall.fen;
 }
 
 async function opponentMove(src, dest) {
     if (game.turn() === playerColorShort) return;
     try {
         const fen = await fetchBoard(src, dest);
-        game.load(fen);
+        loadFen(fen);
         board.position(game.fen());
     } catch (e) {
         console.error(e);
@@ -66,7 +167,8 @@ async function opponentMove(src, dest) {
 }
 
 async function checkGameOver() {
-    if (!game.game_over()) {
+    // Only the server knows if a variant game is over
+    if (!serverRules && !game.game_over() && flagged === null) {
         highlightTurn();
         return;
     }
@@ -75,8 +177,12 @@ async function checkGameOver() {
     if (!response.ok) {
         if (response.status === 406) {
             const fen = await response.text();
-            game.load(fen);
-            board.position(game.fen());
+            if (serverRules) {
+                setVariantFen(fen);
+            } else {
+                loadFen(fen);
+                board.position(game.fen());
+            }
         } else {
             console.error('ERROR: Unexpected Status while fetching game over: ' + response.status);
         }
@@ -84,18 +190,52 @@ async function checkGameOver() {
         return;
     }
 
+    const gameEnd = await response.json();
     let overText = '';
-    if (game.in_checkmate()) overText = (game.turn() === 'w' ? 'White' : 'Black') + ' is Checkmate';
-    if (game.in_draw()) overText = 'Draw';
+    if (flagged !== null) {
+        overText = (flagged === 'white' ? 'White' : 'Black') + ' lost on time';
+    } else if (serverRules) {
+        overText = gameEnd.winner === null ? 'Draw' : (gameEnd.winner === 'white' ? 'White' : 'Black') + ' wins';
+    } else {
+        if (game.in_checkmate()) overText = (game.turn() === 'w' ? 'White' : 'Black') + ' is Checkmate';
+        if (game.in_draw()) overText = 'Draw';
+    }
+    if (gameEnd.hints_used > 0) overText += ` (${gameEnd.hints_used} ${gameEnd.hints_used === 1 ? 'hint' : 'hints'} used)`;
+    if (gameEnd.rating !== null) overText += ` - Your rating: ${gameEnd.rating}`;
+    if (!gameEnd.ranked) overText += ' - Unranked';
     document.getElementById('over-description').innerText = overText;
+    if (gameEnd.accuracy !== null) renderAccuracy(gameEnd.accuracy, overText);
+    else if (gameEnd.review_id !== null) showAccuracy(gameEnd.review_id, overText);
 
     fetchScoreboard(1000).then(data => renderScoreboard(data));
     overContainer.style.display = "block";
 }
 
+// A longer game is still reviewed in the background, its accuracy is shown as soon as the review is done
+async function showAccuracy(reviewId, overText) {
+    try {
+        const response = await fetch(`/review/${reviewId}`);
+        if (!response.ok) return;
+        const review = await response.json();
+        if (review.status === 'pending') {
+            setTimeout(() => showAccuracy(reviewId, overText), 2000);
+            return;
+        }
+        if (review.accuracy !== null) renderAccuracy(review.accuracy, overText);
+    } catch (e) {
+        console.error(e);
+    }
+}
+
+function renderAccuracy(accuracy, overText) {
+    const side = playerColorShort === 'w' ? accuracy.white : accuracy.black;
+    document.getElementById('over-description').innerText = `${overText} - Accuracy: ${side.accuracy}% (ACPL ${side.acpl})`;
+}
+
 function highlightTurn() {
-    playerId.style.opacity = game.turn() === playerColorShort ? '100%' : '50%';
-    opponentId.style.opacity = game.turn() !== playerColorShort ? '100%' : '50%';
+    playerId.style.opacity = turn() === playerColorShort ? '100%' : '50%';
+    opponentId.style.opacity = turn() !== playerColorShort ? '100%' : '50%';
+    if (serverRules) return;
 
     if (game.in_check()) {
         const kingCords = [].concat(...game.board()).map((p, index) => {
@@ -115,7 +255,7 @@ function highlightTurn() {
 }
 
 async function cheat() {
-    if (game.game_over()) return;
+    if (serverRules || game.game_over()) return;
 
     const response = await (await fetch("https://chess-api.com/v1", {
         method: "POST", headers: {
@@ -130,9 +270,28 @@ async function cheat() {
     await checkGameOver();
 }
 
+async function showHint() {
+    if ((!serverRules && game.game_over()) || turn() !== playerColorShort) return;
+    try {
+        const response = await fetch("/hint", {method: "POST", cache: "no-cache"});
+        if (!response.ok) throw new Error(`HTTP error! status: ${response.status}`);
+        const hint = await response.json();
+        board.clearCircles();
+        // Drops of crazyhouse have no start square
+        if (!hint.uci.includes('@')) board.addCircle(hint.uci.substring(0, 2));
+        board.addCircle(hint.uci.substring(2, 4));
+        hintButton.innerText = `Hint: ${hint.san}`;
+    } catch (error) {
+        console.error('Failed to fetch hint:', error);
+    }
+}
+
+hintButton.addEventListener('click', showHint);
+
 async function fetchScoreboard(count) {
     try {
-        const response = await fetch(`/scoreboard?count=${count}`);
+        const scoreboard = chess960 ? 'chess960' : variant;
+        const response = await fetch(`/scoreboard?count=${count}${scoreboard !== 'chess' ? `&variant=${scoreboard}` : ''}`);
         if (!response.ok) throw new Error(`HTTP error! status: ${response.status}`);
         return await response.json();
     } catch (error) {
@@ -151,7 +310,7 @@ function renderScoreboard(scoreboard) {
         const tbody = document.createElement('tbody');
 
         const headerRow = document.createElement('tr');
-        ['Rank', 'Winner', 'Score'].forEach(text => {
+        ['Rank', 'Winner', 'Score', 'Accuracy'].forEach(text => {
             const th = document.createElement('th');
             th.textContent = text;
             headerRow.appendChild(th);
@@ -164,7 +323,7 @@ function renderScoreboard(scoreboard) {
             if (entry.winner===player_name){
                 row.setAttribute("class", "player_entry");
             }
-            [index + 1, entry.winner, entry.score].forEach(text => {
+            [index + 1, entry.winner, entry.score, entry.accuracy === null ? '-' : `${entry.accuracy.toFixed(1)}%`].forEach(text => {
                 const td = document.createElement('td');
                 td.textContent = text;
                 row.appendChild(td);
@@ -182,9 +341,41 @@ function renderScoreboard(scoreboard) {
     }
 }
 
+const thinkingText = document.getElementById("thinking");
+const evalFill = document.getElementById("eval-fill");
+
+// Converts a score from the view of white into the winning chance of white in percent
+function whiteWinPercent(score) {
+    if (score.mate !== undefined) return score.mate >= 0 ? 100 : 0;
+    return 50 + 50 * (2 / (1 + Math.exp(-0.00368208 * score.cp)) - 1);
+}
+
+function formatScore(score) {
+    if (score.mate !== undefined) return `#${score.mate}`;
+    return (score.cp >= 0 ? '+' : '') + (score.cp / 100).toFixed(2);
+}
+
+// The engine streams its thinking while it searches its reply
+function followThinking() {
+    const events = new EventSource("/thinking");
+    events.onmessage = (event) => {
+        const thinking = JSON.parse(event.data);
+        if (thinking.type === 'searching') {
+            thinkingText.innerText = 'Thinking...';
+        } else if (thinking.type === 'info') {
+            thinkingText.innerText = `Depth ${thinking.depth}: ${formatScore(thinking.score)} ${thinking.pv.join(' ')}`;
+            evalFill.style.width = `${whiteWinPercent(thinking.score)}%`;
+        } else if (thinking.type === 'move') {
+            thinkingText.innerText = `Played ${thinking.san}`;
+        }
+    };
+}
+
+followThinking();
+
 function firstMove() {
     highlightTurn();
-    if (playerColorShort === "b") opponentMove("0", "0"); else if (cheatMode) cheat();
+    if (serverRules) setVariantFen(variantFen); else if (playerColorShort === "b") opponentMove("0", "0"); else if (cheatMode) cheat();
 }
 
 firstMove();
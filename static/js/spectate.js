@@ -0,0 +1,43 @@
+const whiteId = document.getElementById("player");
+const blackId = document.getElementById("opponent");
+const resultText = document.getElementById("result");
+const movesText = document.getElementById("moves");
+const exhibitionId = document.getElementById("hidden-id").value;
+
+// Spectators can only watch, so the pieces can not be dragged
+const board = Chessboard2('board', {
+    draggable: false,
+    moveSpeed: 'slow',
+    orientation: 'white',
+    position: 'start',
+});
+
+function renderMoves(moves) {
+    movesText.innerText = moves.map((san, index) => index % 2 === 0 ? `${index / 2 + 1}. ${san}` : san).join(' ');
+}
+
+function highlightTurn(fen) {
+    const turn = fen.split(' ')[1];
+    whiteId.style.opacity = turn === 'w' ? '100%' : '50%';
+    blackId.style.opacity = turn === 'b' ? '100%' : '50%';
+}
+
+// The server sends the whole state after every move, so a reconnecting screen is up to date right away
+const events = new EventSource(`/exhibition/${exhibitionId}/events`);
+events.onmessage = (event) => {
+    const state = JSON.parse(event.data);
+    board.position(state.fen);
+    renderMoves(state.moves);
+    if (state.result === null) {
+        highlightTurn(state.fen);
+        return;
+    }
+    resultText.innerText = state.result === '*' ? 'aborted' : state.result;
+    whiteId.style.opacity = '100%';
+    blackId.style.opacity = '100%';
+    events.close();
+};
+events.onerror = () => {
+    // The exhibition is gone once it has been finished for a while
+    if (events.readyState === EventSource.CLOSED) resultText.innerText = 'no longer available';
+};